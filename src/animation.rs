@@ -0,0 +1,378 @@
+use crate::{
+    camera::Camera, canvas::Color, matrices::Transform, shapes::NoInverseError, world::World,
+    Point, Vector,
+};
+
+/// How a value moves from one keyframe towards the next.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub enum Easing {
+    #[default]
+    Linear,
+    /// Starts slowly and speeds up.
+    EaseIn,
+    /// Starts quickly and slows down.
+    EaseOut,
+    /// Slow at both ends.
+    EaseInOut,
+    /// Holds the value until the next keyframe, then jumps.
+    Step,
+}
+
+impl Easing {
+    /// Maps progress `t` through a segment, from 0 to 1, onto how far the
+    /// value has moved.
+    pub fn apply(&self, t: f64) -> f64 {
+        let t = t.clamp(0.0, 1.0);
+        match self {
+            Easing::Linear => t,
+            Easing::EaseIn => t * t,
+            Easing::EaseOut => 1.0 - (1.0 - t) * (1.0 - t),
+            Easing::EaseInOut => t * t * (3.0 - 2.0 * t),
+            Easing::Step => 0.0,
+        }
+    }
+}
+
+/// Values that can be blended between keyframes.
+pub trait Interpolate: Clone {
+    /// Returns `self` at `t = 0` and `other` at `t = 1`.
+    fn interpolate(&self, other: &Self, t: f64) -> Self;
+}
+
+impl Interpolate for f64 {
+    fn interpolate(&self, other: &Self, t: f64) -> Self {
+        self + (other - self) * t
+    }
+}
+
+impl Interpolate for Color {
+    fn interpolate(&self, other: &Self, t: f64) -> Self {
+        *self + (*other - *self) * t
+    }
+}
+
+impl Interpolate for Point {
+    fn interpolate(&self, other: &Self, t: f64) -> Self {
+        *self + (*other - *self) * t
+    }
+}
+
+impl Interpolate for Vector {
+    fn interpolate(&self, other: &Self, t: f64) -> Self {
+        *self + (*other - *self) * t
+    }
+}
+
+impl Interpolate for Transform {
+    fn interpolate(&self, other: &Self, t: f64) -> Self {
+        self.slerp(other, t)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Keyframe<T> {
+    pub time: f64,
+    pub value: T,
+    /// How the value moves from here to the next keyframe.
+    pub easing: Easing,
+}
+
+/// A value that changes over time, set at keyframes and interpolated between
+/// them. Before the first keyframe and after the last it holds still.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Track<T> {
+    keyframes: Vec<Keyframe<T>>,
+}
+
+impl<T: Interpolate> Track<T> {
+    pub fn new() -> Self {
+        Track {
+            keyframes: Vec::new(),
+        }
+    }
+
+    pub fn key(self, time: f64, value: T) -> Self {
+        self.key_with_easing(time, value, Easing::default())
+    }
+
+    pub fn key_with_easing(mut self, time: f64, value: T, easing: Easing) -> Self {
+        let index = self
+            .keyframes
+            .partition_point(|keyframe| keyframe.time <= time);
+        self.keyframes.insert(
+            index,
+            Keyframe {
+                time,
+                value,
+                easing,
+            },
+        );
+        self
+    }
+
+    pub fn keyframes(&self) -> &[Keyframe<T>] {
+        &self.keyframes
+    }
+
+    /// Returns `None` if there are no keyframes.
+    pub fn value_at(&self, time: f64) -> Option<T> {
+        let next = self
+            .keyframes
+            .partition_point(|keyframe| keyframe.time <= time);
+        if next == 0 {
+            return self
+                .keyframes
+                .first()
+                .map(|keyframe| keyframe.value.clone());
+        }
+        let from = &self.keyframes[next - 1];
+        let Some(to) = self.keyframes.get(next) else {
+            return Some(from.value.clone());
+        };
+
+        let progress = (time - from.time) / (to.time - from.time);
+        Some(
+            from.value
+                .interpolate(&to.value, from.easing.apply(progress)),
+        )
+    }
+}
+
+impl<T: Interpolate> Default for Track<T> {
+    fn default() -> Self {
+        Track::new()
+    }
+}
+
+/// A material setting that can be animated.
+#[derive(Debug, Clone, PartialEq)]
+pub enum MaterialTrack {
+    Color(Track<Color>),
+    Ambient(Track<f64>),
+    Diffuse(Track<f64>),
+    Specular(Track<f64>),
+    Shininess(Track<f64>),
+    Reflective(Track<f64>),
+    Transparency(Track<f64>),
+    RefractiveIndex(Track<f64>),
+    Emissive(Track<Color>),
+}
+
+/// Animates the shape at `index` in the world's objects.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ShapeAnimation {
+    pub index: usize,
+    pub transform: Option<Track<Transform>>,
+    pub material: Vec<MaterialTrack>,
+}
+
+impl ShapeAnimation {
+    pub fn new(index: usize) -> Self {
+        ShapeAnimation {
+            index,
+            transform: None,
+            material: Vec::new(),
+        }
+    }
+}
+
+/// Everything that changes over the course of an animation. Anything without
+/// a track is left as it is.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Animation {
+    pub camera_transform: Option<Track<Transform>>,
+    pub light_position: Option<Track<Point>>,
+    pub light_intensity: Option<Track<Color>>,
+    pub shapes: Vec<ShapeAnimation>,
+}
+
+#[derive(Default, Debug, Copy, Clone, PartialEq, Eq)]
+pub struct NoSuchShapeError;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum AnimationError {
+    NoInverse(NoInverseError),
+    NoSuchShape(NoSuchShapeError),
+}
+
+impl Animation {
+    pub fn new() -> Self {
+        Animation::default()
+    }
+
+    /// Poses `world` and `camera` as they are at `time`.
+    pub fn apply(
+        &self,
+        time: f64,
+        world: &mut World,
+        camera: &mut Camera,
+    ) -> Result<(), AnimationError> {
+        if let Some(transform) = value_at(&self.camera_transform, time) {
+            camera
+                .set_transform(transform)
+                .map_err(|_| AnimationError::NoInverse(NoInverseError))?;
+        }
+        if let Some(position) = value_at(&self.light_position, time) {
+            world.light.position = position;
+        }
+        if let Some(intensity) = value_at(&self.light_intensity, time) {
+            world.light.intensity = intensity;
+        }
+
+        for animation in &self.shapes {
+            let shape = world
+                .objects
+                .get_mut(animation.index)
+                .ok_or(AnimationError::NoSuchShape(NoSuchShapeError))?;
+            if let Some(transform) = value_at(&animation.transform, time) {
+                shape
+                    .set_transform(transform)
+                    .map_err(|_| AnimationError::NoInverse(NoInverseError))?;
+            }
+
+            let material = &mut shape.material;
+            for track in &animation.material {
+                match track {
+                    MaterialTrack::Color(track) => set(&mut material.color, track, time),
+                    MaterialTrack::Ambient(track) => set(&mut material.ambient, track, time),
+                    MaterialTrack::Diffuse(track) => set(&mut material.diffuse, track, time),
+                    MaterialTrack::Specular(track) => set(&mut material.specular, track, time),
+                    MaterialTrack::Shininess(track) => set(&mut material.shininess, track, time),
+                    MaterialTrack::Reflective(track) => set(&mut material.reflective, track, time),
+                    MaterialTrack::Transparency(track) => {
+                        set(&mut material.transparaency, track, time)
+                    }
+                    MaterialTrack::RefractiveIndex(track) => {
+                        set(&mut material.refractive_index, track, time)
+                    }
+                    MaterialTrack::Emissive(track) => set(&mut material.emissive, track, time),
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn value_at<T: Interpolate>(track: &Option<Track<T>>, time: f64) -> Option<T> {
+    track.as_ref().and_then(|track| track.value_at(time))
+}
+
+fn set<T: Interpolate>(field: &mut T, track: &Track<T>, time: f64) {
+    if let Some(value) = track.value_at(time) {
+        *field = value;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::f64::consts::PI;
+
+    use crate::{
+        canvas::{BLACK, WHITE},
+        shapes::{Shape, Sphere},
+        transformations::{scaling, translation},
+        EQUALITY_EPSILON,
+    };
+
+    use super::*;
+
+    #[test]
+    fn easing_curves_meet_at_the_ends() {
+        for easing in [
+            Easing::Linear,
+            Easing::EaseIn,
+            Easing::EaseOut,
+            Easing::EaseInOut,
+        ] {
+            assert_eq!(easing.apply(0.0), 0.0);
+            assert_eq!(easing.apply(1.0), 1.0);
+        }
+        assert_eq!(Easing::EaseIn.apply(0.5), 0.25);
+        assert_eq!(Easing::EaseOut.apply(0.5), 0.75);
+        assert_eq!(Easing::EaseInOut.apply(0.5), 0.5);
+        assert_eq!(Easing::Step.apply(0.99), 0.0);
+    }
+
+    #[test]
+    fn empty_track_has_no_value() {
+        let track = Track::<f64>::new();
+        assert_eq!(track.value_at(1.0), None);
+    }
+
+    #[test]
+    fn track_holds_still_outside_its_keyframes() {
+        let track = Track::new().key(1.0, 10.0).key(2.0, 20.0);
+        assert_eq!(track.value_at(0.0), Some(10.0));
+        assert_eq!(track.value_at(3.0), Some(20.0));
+    }
+
+    #[test]
+    fn track_interpolates_between_keyframes() {
+        let track =
+            Track::new()
+                .key(2.0, 20.0)
+                .key(0.0, 0.0)
+                .key_with_easing(1.0, 10.0, Easing::EaseIn);
+        assert_eq!(track.keyframes()[1].time, 1.0);
+        assert_eq!(track.value_at(0.5), Some(5.0));
+        assert_eq!(track.value_at(1.0), Some(10.0));
+        assert_eq!(track.value_at(1.5), Some(12.5));
+    }
+
+    #[test]
+    fn step_track_jumps_at_keyframes() {
+        let track = Track::new()
+            .key_with_easing(0.0, BLACK, Easing::Step)
+            .key(1.0, WHITE);
+        assert_eq!(track.value_at(0.9), Some(BLACK));
+        assert_eq!(track.value_at(1.0), Some(WHITE));
+    }
+
+    #[test]
+    fn apply_poses_world_and_camera() {
+        let mut world = World::new();
+        world.objects = vec![Shape::new(Sphere)];
+        let mut camera = Camera::new(10, 10, PI / 2.0);
+        let mut ball = ShapeAnimation::new(0);
+        ball.transform = Some(
+            Track::new()
+                .key(0.0, translation(0.0, 0.0, 0.0))
+                .key(2.0, translation(0.0, 4.0, 0.0)),
+        );
+        ball.material = vec![MaterialTrack::Reflective(
+            Track::new().key(0.0, 0.0).key(2.0, 1.0),
+        )];
+        let animation = Animation {
+            camera_transform: Some(Track::new().key(0.0, scaling(2.0, 2.0, 2.0))),
+            light_position: Some(
+                Track::new()
+                    .key(0.0, Point::new(0.0, 0.0, 0.0))
+                    .key(1.0, Point::new(0.0, 10.0, 0.0)),
+            ),
+            light_intensity: None,
+            shapes: vec![ball],
+        };
+
+        animation.apply(0.5, &mut world, &mut camera).unwrap();
+        assert_eq!(camera.transform(), &scaling(2.0, 2.0, 2.0));
+        assert_eq!(world.light.position, Point::new(0.0, 5.0, 0.0));
+        assert_eq!(
+            world.objects[0].get_transform(),
+            &translation(0.0, 1.0, 0.0)
+        );
+        assert!((world.objects[0].material.reflective - 0.25).abs() < EQUALITY_EPSILON);
+    }
+
+    #[test]
+    fn apply_rejects_missing_shape() {
+        let mut world = World::new();
+        let mut camera = Camera::new(10, 10, PI / 2.0);
+        let mut animation = Animation::new();
+        animation.shapes.push(ShapeAnimation::new(3));
+        assert_eq!(
+            animation.apply(0.0, &mut world, &mut camera),
+            Err(AnimationError::NoSuchShape(NoSuchShapeError))
+        );
+    }
+}
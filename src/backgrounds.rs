@@ -0,0 +1,171 @@
+use crate::{
+    canvas::{Color, BLACK},
+    patterns::{spherical_map, CubeMap, PatternModel, UvImage, UvPatternModel},
+    Point, Vector,
+};
+
+/// What a ray sees when it doesn't hit anything in the world.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Background {
+    Solid(Color),
+    /// Blends from `bottom` looking straight down to `top` looking straight up.
+    Gradient {
+        bottom: Color,
+        top: Color,
+    },
+    /// Six images making up the inside of a cube around the scene.
+    CubeMap(Box<CubeMap<UvImage>>),
+    /// A single 360° image, with longitude along `u` and latitude along `v`.
+    Equirectangular(UvImage),
+}
+
+impl Background {
+    /// A zero-length direction is taken as looking along +z, towards the
+    /// middle of the horizon.
+    pub fn color_for(&self, direction: Vector) -> Color {
+        let direction = if direction.magnitude() == 0.0 {
+            Vector::new(0.0, 0.0, 1.0)
+        } else {
+            direction.normalize()
+        };
+        match self {
+            Background::Solid(color) => *color,
+            Background::Gradient { bottom, top } => {
+                let fraction = (direction.y + 1.0) / 2.0;
+                *bottom + (*top - *bottom) * fraction
+            }
+            Background::CubeMap(cube_map) => {
+                // Push the direction out onto the surface of the unit cube
+                let largest = direction
+                    .x
+                    .abs()
+                    .max(direction.y.abs())
+                    .max(direction.z.abs());
+                let on_cube = direction / largest;
+                cube_map.at(Point::new(on_cube.x, on_cube.y, on_cube.z))
+            }
+            Background::Equirectangular(image) => {
+                let (u, v) = spherical_map(Point::new(direction.x, direction.y, direction.z));
+                // Seen from inside the sphere, so longitude runs the other way
+                image.uv_at(1.0 - u, v)
+            }
+        }
+    }
+}
+
+impl Default for Background {
+    fn default() -> Self {
+        Background::Solid(BLACK)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::canvas::{Canvas, WHITE};
+
+    use super::*;
+
+    fn solid_image(color: Color) -> UvImage {
+        let mut canvas = Canvas::new(2, 2);
+        for x in 0..2 {
+            for y in 0..2 {
+                canvas.write_pixel(x, y, color).unwrap();
+            }
+        }
+        UvImage::new(canvas)
+    }
+
+    #[test]
+    fn default_background_is_black() {
+        let background = Background::default();
+        assert_eq!(background.color_for(Vector::new(0.0, 0.0, 1.0)), BLACK);
+    }
+
+    #[test]
+    fn solid_background() {
+        let color = Color::new(0.2, 0.4, 0.8);
+        let background = Background::Solid(color);
+        assert_eq!(background.color_for(Vector::new(1.0, 2.0, 3.0)), color);
+    }
+
+    #[test]
+    fn gradient_background_blends_by_height() {
+        let background = Background::Gradient {
+            bottom: BLACK,
+            top: WHITE,
+        };
+        assert_eq!(background.color_for(Vector::new(0.0, -1.0, 0.0)), BLACK);
+        assert_eq!(
+            background.color_for(Vector::new(0.0, 0.0, 1.0)),
+            Color::new(0.5, 0.5, 0.5)
+        );
+        assert_eq!(background.color_for(Vector::new(0.0, 5.0, 0.0)), WHITE);
+    }
+
+    #[test]
+    fn zero_direction_looks_at_the_horizon() {
+        let background = Background::Gradient {
+            bottom: BLACK,
+            top: WHITE,
+        };
+        assert_eq!(
+            background.color_for(Vector::new(0.0, 0.0, 0.0)),
+            Color::new(0.5, 0.5, 0.5)
+        );
+    }
+
+    #[test]
+    fn cube_map_background_looks_up_faces_by_direction() {
+        let left = Color::new(1.0, 0.0, 0.0);
+        let front = Color::new(0.0, 1.0, 0.0);
+        let right = Color::new(0.0, 0.0, 1.0);
+        let back = Color::new(1.0, 1.0, 0.0);
+        let up = Color::new(0.0, 1.0, 1.0);
+        let down = Color::new(1.0, 0.0, 1.0);
+        let background = Background::CubeMap(Box::new(CubeMap::new(
+            solid_image(left),
+            solid_image(front),
+            solid_image(right),
+            solid_image(back),
+            solid_image(up),
+            solid_image(down),
+        )));
+        assert_eq!(background.color_for(Vector::new(-1.0, 0.2, 0.3)), left);
+        assert_eq!(background.color_for(Vector::new(0.1, 0.2, 3.0)), front);
+        assert_eq!(background.color_for(Vector::new(2.0, -0.5, 0.3)), right);
+        assert_eq!(background.color_for(Vector::new(0.1, 0.2, -0.5)), back);
+        assert_eq!(background.color_for(Vector::new(0.1, 0.9, 0.3)), up);
+        assert_eq!(background.color_for(Vector::new(0.1, -0.9, 0.3)), down);
+    }
+
+    #[test]
+    fn equirectangular_background_maps_latitude_and_longitude() {
+        let mut canvas = Canvas::new(4, 3);
+        let sky = Color::new(0.5, 0.7, 1.0);
+        let ground = Color::new(0.3, 0.2, 0.1);
+        for x in 0..4 {
+            canvas.write_pixel(x, 0, sky).unwrap();
+            canvas.write_pixel(x, 2, ground).unwrap();
+        }
+        let background = Background::Equirectangular(UvImage::new(canvas));
+        assert_eq!(background.color_for(Vector::new(0.0, 1.0, 0.0)), sky);
+        assert_eq!(background.color_for(Vector::new(0.0, -1.0, 0.0)), ground);
+        assert_eq!(background.color_for(Vector::new(1.0, 0.0, 0.0)), BLACK);
+    }
+
+    #[test]
+    fn equirectangular_background_runs_left_to_right_from_inside() {
+        let mut canvas = Canvas::new(5, 1);
+        let left = Color::new(1.0, 0.0, 0.0);
+        let ahead = Color::new(0.0, 1.0, 0.0);
+        let right = Color::new(0.0, 0.0, 1.0);
+        canvas.write_pixel(1, 0, left).unwrap();
+        canvas.write_pixel(2, 0, ahead).unwrap();
+        canvas.write_pixel(3, 0, right).unwrap();
+        let background = Background::Equirectangular(UvImage::new(canvas));
+        // Looking along +z with +y up, +x is on the right
+        assert_eq!(background.color_for(Vector::new(0.0, 0.0, 1.0)), ahead);
+        assert_eq!(background.color_for(Vector::new(1.0, 0.0, 0.0)), right);
+        assert_eq!(background.color_for(Vector::new(-1.0, 0.0, 0.0)), left);
+    }
+}
@@ -1,15 +1,21 @@
 use clap::Parser;
-use std::f64::consts::PI;
+use std::{f64::consts::PI, ops::ControlFlow, time::Duration};
 
 use ray_tracer_challenge::{
-    camera::Camera,
-    canvas::Color,
+    animation::{Animation, Easing, MaterialTrack, ShapeAnimation, Track},
+    backgrounds::Background,
+    camera::{
+        CameraBuilder, Crop, CropOutOfBoundsError, Integrator, Projection, DEFAULT_FOCAL_DISTANCE,
+    },
+    canvas::{Canvas, Color},
     lights::PointLight,
     materials::Material,
-    patterns::{Checkers, Gradient, Pattern, Rings},
+    media::Medium,
+    patterns::{Checkers, Gradient, Pattern, Rings, UvImage},
+    progress::{CancellationToken, CancelledError, Progress},
     shapes::{Plane, Shape, Sphere},
     transformations::{translation, view_transform, Builder},
-    world::World,
+    world::{AmbientOcclusion, World},
     Point, Vector,
 };
 
@@ -23,6 +29,89 @@ struct Args {
 
     #[arg(long, default_value = "270")]
     height: usize,
+
+    /// Equirectangular PPM image to use as the sky
+    #[arg(long)]
+    skybox: Option<String>,
+
+    /// Use Monte Carlo path tracing instead of Whitted-style ray tracing
+    #[arg(long)]
+    path_trace: bool,
+
+    #[arg(long, default_value = "1")]
+    samples: usize,
+
+    /// Give up after this many seconds. A progressive render keeps its last
+    /// pass and an animation the frames already written; otherwise nothing
+    /// is written
+    #[arg(long, value_parser = seconds)]
+    time_limit: Option<f64>,
+
+    /// Render only this rectangle of the image, in pixels from the top left
+    #[arg(long, num_args = 4, value_names = ["X", "Y", "WIDTH", "HEIGHT"])]
+    crop: Option<Vec<usize>>,
+
+    /// Refine the image in passes, overwriting the output after each one so
+    /// it can be previewed, and the render stopped once it looks done
+    #[arg(long)]
+    progressive: bool,
+
+    /// Focal length in millimetres, on a full-frame sensor
+    #[arg(long, default_value = "37")]
+    focal_length: f64,
+
+    /// Lens radius; anything above zero blurs what's out of focus
+    #[arg(long, default_value = "0")]
+    aperture: f64,
+
+    /// Distance from the camera to the plane in focus
+    #[arg(long, default_value_t = DEFAULT_FOCAL_DISTANCE)]
+    focal_distance: f64,
+
+    /// Use parallel rays covering this many units across the image
+    #[arg(long)]
+    orthographic: Option<f64>,
+
+    /// Render the full sphere around the camera as an equirectangular image
+    #[arg(long)]
+    panorama: bool,
+
+    /// Use a fisheye lens covering this many degrees
+    #[arg(long)]
+    fisheye: Option<f64>,
+
+    /// How long the shutter stays open, blurring the small green ball as it
+    /// slides along; 1 covers its whole motion
+    #[arg(long, default_value = "0")]
+    shutter: f64,
+
+    /// Bounces per camera ray, overriding the integrator's default
+    #[arg(long)]
+    max_depth: Option<usize>,
+
+    /// Density of a light grey fog filling the scene
+    #[arg(long)]
+    fog: Option<f64>,
+
+    /// Rays per point for ambient occlusion, which is off without this
+    #[arg(long)]
+    ambient_occlusion: Option<usize>,
+
+    /// Render the demo animation from this frame, writing numbered frames
+    /// instead of a single image
+    #[arg(long)]
+    first_frame: Option<usize>,
+
+    /// Last frame of the animation to render
+    #[arg(long, default_value = "96")]
+    last_frame: usize,
+
+    #[arg(long, default_value = "24")]
+    fps: f64,
+
+    /// Directory the animation frames are written to
+    #[arg(long, default_value = ".")]
+    frame_dir: String,
 }
 
 fn main() -> std::io::Result<()> {
@@ -84,6 +173,14 @@ fn main() -> std::io::Result<()> {
                 .transform(),
         )
         .unwrap();
+    right
+        .set_motion(
+            Builder::new()
+                .scaling(0.5, 0.5, 0.5)
+                .translation(1.0, 0.5, -0.5)
+                .transform(),
+        )
+        .unwrap();
     right.material = Material::new();
     right.material.color = Color::new(0.5, 1.0, 0.1);
     right.material.diffuse = 0.7;
@@ -120,21 +217,195 @@ fn main() -> std::io::Result<()> {
     left.material.refractive_index = 1.05;
 
     let mut world = World::new();
-    world.objects = vec![floor, back_wall, middle, right, left];
+    world.objects = vec![floor, back_wall];
+    // The demo animation needs to know where the shapes it moves end up
+    let middle_index = world.objects.len();
+    world.objects.push(middle);
+    world.objects.push(right);
+    let left_index = world.objects.len();
+    world.objects.push(left);
     world.light = PointLight::new(Point::new(-10.0, 10.0, -10.0), Color::new(1.0, 1.0, 1.0));
+    world.background = match args.skybox {
+        Some(path) => {
+            let ppm = std::fs::read_to_string(path)?;
+            let canvas = Canvas::from_ppm(&ppm).map_err(|_| {
+                std::io::Error::new(std::io::ErrorKind::InvalidData, "invalid skybox image")
+            })?;
+            Background::Equirectangular(UvImage::new(canvas))
+        }
+        None => Background::Gradient {
+            bottom: Color::new(0.9, 0.9, 0.95),
+            top: Color::new(0.3, 0.5, 0.9),
+        },
+    };
+    world.fog = args
+        .fog
+        .map(|density| Medium::new(density, Color::new(0.8, 0.8, 0.85)));
+    world.ambient_occlusion = args
+        .ambient_occlusion
+        .map(|samples| AmbientOcclusion::new(samples, 1.0));
 
-    let mut camera = Camera::new(args.width, args.height, PI / 3.0);
-    camera
-        .set_transform(view_transform(
-            Point::new(0.0, 1.5, -5.0),
-            Point::new(0.0, 1.0, 0.0),
-            Vector::new(0.0, 1.0, 0.0),
-        ))
+    let mut camera = CameraBuilder::new(args.width, args.height)
+        .position(Point::new(0.0, 1.5, -5.0))
+        .target(Point::new(0.0, 1.0, 0.0))
+        .up(Vector::new(0.0, 1.0, 0.0))
+        .focal_length(args.focal_length)
+        .build()
         .expect("no inverse error");
+    if args.path_trace {
+        camera.integrator = Integrator::PathTracing;
+    }
+    camera.samples_per_pixel = args.samples;
+    camera.max_depth = args.max_depth;
+    if let Some(width) = args.orthographic {
+        camera.set_projection(Projection::Orthographic { width });
+    } else if args.panorama {
+        camera.set_projection(Projection::Equirectangular);
+    } else if let Some(degrees) = args.fisheye {
+        camera.set_projection(Projection::Fisheye {
+            field_of_view: degrees.to_radians(),
+        });
+    }
+    if let Some(crop) = args.crop {
+        camera
+            .set_crop(Some(Crop::new(crop[0], crop[1], crop[2], crop[3])))
+            .map_err(|CropOutOfBoundsError| {
+                std::io::Error::new(
+                    std::io::ErrorKind::InvalidInput,
+                    "crop doesn't fit in the image",
+                )
+            })?;
+    }
+    camera.shutter_close = args.shutter;
+    camera.aperture = args.aperture;
+    camera.focal_distance = args.focal_distance;
+
+    // Stops the render once time runs out
+    let cancel = CancellationToken::new();
+    if let Some(seconds) = args.time_limit {
+        let cancel = cancel.clone();
+        std::thread::spawn(move || {
+            std::thread::sleep(Duration::from_secs_f64(seconds));
+            cancel.cancel();
+        });
+    }
+
+    let Some(first_frame) = args.first_frame else {
+        if !args.progressive {
+            match camera.render_with_progress(&world, &cancel, print_progress) {
+                Ok(canvas) => std::fs::write(args.output, canvas.to_ppm())?,
+                Err(CancelledError) => eprintln!("\nout of time, nothing written"),
+            }
+            eprintln!();
+            return Ok(());
+        }
 
-    let canvas = camera.render(&world);
+        let mut written = Ok(());
+        let rendered =
+            camera.render_progressive(&world, &cancel, print_progress, |canvas, samples| {
+                written = std::fs::write(&args.output, canvas.to_ppm());
+                eprintln!(
+                    "\n{samples} of {} samples per pixel",
+                    camera.samples_per_pixel
+                );
+                if written.is_err() {
+                    ControlFlow::Break(())
+                } else {
+                    ControlFlow::Continue(())
+                }
+            });
+        match rendered {
+            Ok(_) if cancel.is_cancelled() => eprintln!("\nout of time, kept the last pass"),
+            Ok(_) => {}
+            Err(CancelledError) => eprintln!("\nout of time, nothing written"),
+        }
+        return written;
+    };
 
-    std::fs::write(args.output, canvas.to_ppm())?;
+    std::fs::create_dir_all(&args.frame_dir)?;
+    let animation = demo_animation(middle_index, left_index);
+    for frame in first_frame..=args.last_frame {
+        animation
+            .apply(frame as f64 / args.fps, &mut world, &mut camera)
+            .expect("invalid animation");
+        eprint!("frame {frame:04} ");
+        let Ok(canvas) = camera.render_with_progress(&world, &cancel, print_progress) else {
+            eprintln!("\nout of time");
+            break;
+        };
+        eprintln!();
+        let path = std::path::Path::new(&args.frame_dir).join(format!("frame_{frame:04}.ppm"));
+        std::fs::write(path, canvas.to_ppm())?;
+    }
 
     Ok(())
 }
+
+/// Parses a length of time in seconds, which has to be finite and can't be
+/// negative.
+fn seconds(value: &str) -> Result<f64, String> {
+    let seconds = value.parse::<f64>().map_err(|error| error.to_string())?;
+    if seconds.is_finite() && seconds >= 0.0 {
+        Ok(seconds)
+    } else {
+        Err("must be zero or more seconds".to_string())
+    }
+}
+
+/// Redraws a progress bar on the current line of stderr.
+fn print_progress(progress: &Progress) {
+    const WIDTH: usize = 30;
+    let filled = (progress.fraction() * WIDTH as f64) as usize;
+    let eta = match progress.eta() {
+        Some(eta) => format!("{:.0}s", eta.as_secs_f64()),
+        None => "?".to_string(),
+    };
+    eprint!(
+        "\r[{}{}] {}/{} rows, {:.0}s elapsed, {eta} left ",
+        "#".repeat(filled),
+        " ".repeat(WIDTH - filled),
+        progress.rows_done,
+        progress.rows,
+        progress.elapsed.as_secs_f64(),
+    );
+}
+
+/// Four seconds of the camera swinging around the scene while the middle
+/// sphere bobs, the glass sphere turns to a mirror and the light warms. The
+/// spheres are found at `middle_index` and `left_index` in the world's objects.
+fn demo_animation(middle_index: usize, left_index: usize) -> Animation {
+    let target = Point::new(0.0, 1.0, 0.0);
+    let up = Vector::new(0.0, 1.0, 0.0);
+    let mut camera_transform = Track::new();
+    for step in 0..=8 {
+        let angle = (step as f64 / 8.0 - 0.5) * PI / 2.0;
+        let from = Point::new(5.0 * angle.sin(), 1.5, -5.0 * angle.cos());
+        camera_transform =
+            camera_transform.key(step as f64 * 0.5, view_transform(from, target, up));
+    }
+
+    let mut middle = ShapeAnimation::new(middle_index);
+    middle.transform = Some(
+        Track::new()
+            .key_with_easing(0.0, translation(-0.5, 1.0, 3.0), Easing::EaseInOut)
+            .key_with_easing(2.0, translation(-0.5, 2.0, 3.0), Easing::EaseInOut)
+            .key(4.0, translation(-0.5, 1.0, 3.0)),
+    );
+
+    let mut left = ShapeAnimation::new(left_index);
+    left.material = vec![
+        MaterialTrack::Transparency(Track::new().key(1.0, 0.9).key(3.0, 0.0)),
+        MaterialTrack::Reflective(Track::new().key(1.0, 0.1).key(3.0, 0.9)),
+    ];
+
+    Animation {
+        camera_transform: Some(camera_transform),
+        light_position: None,
+        light_intensity: Some(
+            Track::new()
+                .key(0.0, Color::new(1.0, 1.0, 1.0))
+                .key(4.0, Color::new(1.0, 0.8, 0.6)),
+        ),
+        shapes: vec![middle, left],
+    }
+}
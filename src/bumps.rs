@@ -0,0 +1,98 @@
+use crate::{canvas::Color, patterns::Pattern, shapes::Shape, Point, Vector};
+
+const BUMP_EPSILON: f64 = 0.001;
+
+/// Perturbs surface normals using a pattern as a height field, so flat
+/// geometry can look rippled or rough.
+///
+/// The brightness of the pattern is the height at each point. Noise patterns
+/// like `Turbulence` give procedural bumps, and a `TextureMap` of a `UvImage`
+/// gives a height map wrapped around the shape.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Bump {
+    pub pattern: Pattern,
+    pub strength: f64,
+}
+
+impl Bump {
+    pub fn new(pattern: Pattern, strength: f64) -> Self {
+        Bump { pattern, strength }
+    }
+
+    pub fn height_at(&self, object: &Shape, point: Point, time: f64) -> f64 {
+        luminance(self.pattern.at_shape(object, point, time))
+    }
+
+    /// Tilts `normal` away from the direction the height field rises in, on
+    /// `object` as it is at `time`.
+    pub fn perturb(&self, object: &Shape, point: Point, normal: Vector, time: f64) -> Vector {
+        let height = self.height_at(object, point, time);
+        let slope =
+            |offset: Vector| (self.height_at(object, point + offset, time) - height) / BUMP_EPSILON;
+        let gradient = Vector::new(
+            slope(Vector::new(BUMP_EPSILON, 0.0, 0.0)),
+            slope(Vector::new(0.0, BUMP_EPSILON, 0.0)),
+            slope(Vector::new(0.0, 0.0, BUMP_EPSILON)),
+        );
+
+        // Only the part of the slope along the surface should tilt the normal
+        let tangential = gradient - normal * Vector::dot(gradient, normal);
+        (normal - tangential * self.strength).normalize()
+    }
+}
+
+fn luminance(color: Color) -> f64 {
+    (color.red() + color.green() + color.blue()) / 3.0
+}
+
+#[cfg(test)]
+mod test {
+    use std::f64::consts::FRAC_1_SQRT_2;
+
+    use crate::{
+        canvas::{BLACK, WHITE},
+        patterns::Gradient,
+        shapes::{Plane, Sphere},
+        transformations::scaling,
+        EQUALITY_EPSILON,
+    };
+
+    use super::*;
+
+    #[test]
+    fn flat_height_leaves_normal_alone() {
+        let bump = Bump::new(Pattern::from(WHITE), 1.0);
+        let shape = Shape::new(Sphere);
+        let normal = Vector::new(0.0, 0.0, -1.0);
+        let perturbed = bump.perturb(&shape, Point::new(0.0, 0.0, -1.0), normal, 0.0);
+        assert_eq!(perturbed, normal);
+    }
+
+    #[test]
+    fn normal_tilts_away_from_rising_height() {
+        let bump = Bump::new(Pattern::new(Gradient::new(BLACK, WHITE)), 1.0);
+        let shape = Shape::new(Plane);
+        let normal = Vector::new(0.0, 1.0, 0.0);
+        let perturbed = bump.perturb(&shape, Point::new(0.5, 0.0, 0.5), normal, 0.0);
+        assert_eq!(perturbed, Vector::new(-FRAC_1_SQRT_2, FRAC_1_SQRT_2, 0.0));
+    }
+
+    #[test]
+    fn strength_scales_tilt() {
+        let bump = Bump::new(Pattern::new(Gradient::new(BLACK, WHITE)), 0.0);
+        let shape = Shape::new(Plane);
+        let normal = Vector::new(0.0, 1.0, 0.0);
+        let perturbed = bump.perturb(&shape, Point::new(0.5, 0.0, 0.5), normal, 0.0);
+        assert_eq!(perturbed, normal);
+    }
+
+    #[test]
+    fn height_follows_object_transform() {
+        let bump = Bump::new(Pattern::new(Gradient::new(BLACK, WHITE)), 1.0);
+        let mut shape = Shape::new(Plane);
+        shape.set_transform(scaling(2.0, 1.0, 1.0)).unwrap();
+        assert!(
+            (bump.height_at(&shape, Point::new(1.0, 0.0, 0.0), 0.0) - 0.5).abs() < EQUALITY_EPSILON
+        );
+    }
+}
@@ -1,71 +1,352 @@
+use std::{f64::consts::PI, ops::ControlFlow, time::Instant};
+
 use crate::{
-    canvas::{Canvas, PixelOutOfBoundsError},
+    canvas::{Canvas, Color, PixelOutOfBoundsError, BLACK},
     matrices::{Transform, IDENTITY},
+    progress::{CancellationToken, CancelledError, Progress},
+    random::{self, in_unit_disk, random, Rng},
     rays::Ray,
-    world::{World, RECURSION_DEPTH},
-    Point,
+    transformations::view_transform,
+    world::{RayBudget, World, CONTRIBUTION_CUTOFF, PATH_DEPTH, RECURSION_DEPTH},
+    Point, Vector,
 };
 
+// A 35mm full-frame sensor, in millimetres
+const FULL_FRAME: (f64, f64) = (36.0, 24.0);
+const DEFAULT_FOCAL_LENGTH: f64 = 50.0;
+/// How far in front of the camera things are sharp unless told otherwise,
+/// about where the subject of a typical scene sits.
+pub const DEFAULT_FOCAL_DISTANCE: f64 = 5.0;
+
+/// How the colour seen along each camera ray is worked out.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub enum Integrator {
+    /// Direct lighting plus perfect reflection and refraction.
+    #[default]
+    Whitted,
+    /// Monte Carlo path tracing, which includes indirect light but needs many
+    /// samples per pixel to converge.
+    PathTracing,
+}
+
+/// How points in the scene are projected onto the image.
+#[derive(Debug, Default, Copy, Clone, PartialEq)]
+pub enum Projection {
+    /// Rays spread out from a single point, covering the field of view.
+    #[default]
+    Perspective,
+    /// Parallel rays covering `width` units of the scene across the image,
+    /// so sizes don't change with distance.
+    Orthographic { width: f64 },
+    /// The full sphere around the camera, with longitude across the image and
+    /// latitude down it, laid out like an equirectangular `Background`.
+    Equirectangular,
+    /// An equidistant fisheye, covering `field_of_view` across the shorter
+    /// side of the image. Use π for a full hemisphere.
+    Fisheye { field_of_view: f64 },
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct Camera {
     hsize: usize,
     vsize: usize,
     field_of_view: f64,
+    projection: Projection,
+    sensor_size: (f64, f64),
     transform: Transform,
     inverse: Transform,
     half_width: f64,
     half_height: f64,
     pixel_size: f64,
+    pub integrator: Integrator,
+    /// With more than one sample, each is jittered randomly within the pixel.
+    pub samples_per_pixel: usize,
+    pub seed: u64,
+    /// Bounces allowed per camera ray, defaulting to `RECURSION_DEPTH` or
+    /// `PATH_DEPTH` depending on the integrator.
+    pub max_depth: Option<usize>,
+    /// Whitted rendering stops following reflections and refractions that
+    /// contribute less than this to the pixel.
+    pub min_contribution: f64,
+    /// Radius of the lens. With zero the camera is a pinhole and everything is
+    /// in focus; otherwise use several samples per pixel to smooth the blur.
+    /// Only perspective cameras have a lens.
+    pub aperture: f64,
+    /// How far in front of the camera things are perfectly sharp. Defaults to
+    /// `DEFAULT_FOCAL_DISTANCE`.
+    pub focal_distance: f64,
+    /// Each sample is taken at a random time between these, blurring moving
+    /// shapes. Shapes move between time 0 and 1.
+    pub shutter_open: f64,
+    pub shutter_close: f64,
+    crop: Option<Crop>,
+}
+
+/// A rectangle of pixels within the camera's image.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub struct Crop {
+    pub x: usize,
+    pub y: usize,
+    pub width: usize,
+    pub height: usize,
+}
+
+impl Crop {
+    pub fn new(x: usize, y: usize, width: usize, height: usize) -> Self {
+        Crop {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
 }
 
 #[derive(Default, Debug, Copy, Clone, PartialEq, Eq)]
 pub struct NoInverseError;
 
+#[derive(Default, Debug, Copy, Clone, PartialEq, Eq)]
+pub struct CropOutOfBoundsError;
+
 impl Camera {
     pub fn new(hsize: usize, vsize: usize, field_of_view: f64) -> Self {
-        let half_view = (field_of_view / 2.0).tan();
-        let aspect = hsize as f64 / vsize as f64;
-
-        let half_width;
-        let half_height;
-        if aspect >= 1.0 {
-            half_width = half_view;
-            half_height = half_view / aspect;
-        } else {
-            half_width = half_view * aspect;
-            half_height = half_view;
-        }
-
-        let pixel_size = (half_width * 2.0) / hsize as f64;
+        let projection = Projection::default();
+        let (half_width, half_height, pixel_size) =
+            Self::frame(hsize, vsize, field_of_view, projection);
 
         Camera {
             hsize,
             vsize,
             field_of_view,
+            projection,
+            sensor_size: FULL_FRAME,
             transform: IDENTITY,
             inverse: IDENTITY,
             half_width,
             half_height,
             pixel_size,
+            integrator: Integrator::default(),
+            samples_per_pixel: 1,
+            seed: 0,
+            max_depth: None,
+            min_contribution: CONTRIBUTION_CUTOFF,
+            aperture: 0.0,
+            focal_distance: DEFAULT_FOCAL_DISTANCE,
+            shutter_open: 0.0,
+            shutter_close: 0.0,
+            crop: None,
+        }
+    }
+
+    /// Works out the half width and half height of the image, on the plane one
+    /// unit in front of the camera, along with the size of a pixel there.
+    fn frame(
+        hsize: usize,
+        vsize: usize,
+        field_of_view: f64,
+        projection: Projection,
+    ) -> (f64, f64, f64) {
+        let aspect = hsize as f64 / vsize as f64;
+
+        let half_width;
+        let half_height;
+        match projection {
+            Projection::Perspective | Projection::Equirectangular | Projection::Fisheye { .. } => {
+                let half_view = (field_of_view / 2.0).tan();
+                if aspect >= 1.0 {
+                    half_width = half_view;
+                    half_height = half_view / aspect;
+                } else {
+                    half_width = half_view * aspect;
+                    half_height = half_view;
+                }
+            }
+            Projection::Orthographic { width } => {
+                half_width = width / 2.0;
+                half_height = half_width / aspect;
+            }
         }
+
+        let pixel_size = (half_width * 2.0) / hsize as f64;
+        (half_width, half_height, pixel_size)
     }
 
+    pub fn hsize(&self) -> usize {
+        self.hsize
+    }
+
+    pub fn vsize(&self) -> usize {
+        self.vsize
+    }
+
+    /// The angle covered by the longer side of the image.
+    pub fn field_of_view(&self) -> f64 {
+        self.field_of_view
+    }
+
+    /// The angle covered from the top to the bottom of a perspective image.
+    pub fn vertical_field_of_view(&self) -> f64 {
+        2.0 * self.half_height.atan()
+    }
+
+    /// Sensor width and height in millimetres, used to relate the field of
+    /// view to a focal length.
+    pub fn sensor_size(&self) -> (f64, f64) {
+        self.sensor_size
+    }
+
+    /// The focal length in millimetres giving this field of view on the sensor.
+    pub fn focal_length(&self) -> f64 {
+        self.sensor_size.1 / (2.0 * self.half_height)
+    }
+
+    pub fn transform(&self) -> &Transform {
+        &self.transform
+    }
+
+    pub fn position(&self) -> Point {
+        &self.inverse * Point::new(0.0, 0.0, 0.0)
+    }
+
+    /// The direction the camera is looking in.
+    pub fn direction(&self) -> Vector {
+        (&self.inverse * Vector::new(0.0, 0.0, -1.0)).normalize()
+    }
+
+    /// The direction that's up in the image.
+    pub fn up(&self) -> Vector {
+        (&self.inverse * Vector::new(0.0, 1.0, 0.0)).normalize()
+    }
+
+    pub fn projection(&self) -> Projection {
+        self.projection
+    }
+
+    pub fn set_projection(&mut self, projection: Projection) {
+        self.projection = projection;
+        (self.half_width, self.half_height, self.pixel_size) =
+            Self::frame(self.hsize, self.vsize, self.field_of_view, projection);
+    }
+
+    pub fn crop(&self) -> Option<Crop> {
+        self.crop
+    }
+
+    /// Renders only `crop` of the image, into a canvas its size. Each pixel
+    /// comes out as it would in a render of the whole image.
+    pub fn set_crop(&mut self, crop: Option<Crop>) -> Result<(), CropOutOfBoundsError> {
+        if let Some(crop) = crop {
+            let fits = |start: usize, length: usize, size: usize| {
+                start.checked_add(length).is_some_and(|end| end <= size)
+            };
+            if !fits(crop.x, crop.width, self.hsize) || !fits(crop.y, crop.height, self.vsize) {
+                return Err(CropOutOfBoundsError);
+            }
+        }
+        self.crop = crop;
+        Ok(())
+    }
+
+    /// The part of the image to render.
+    fn region(&self) -> Crop {
+        self.crop.unwrap_or(Crop::new(0, 0, self.hsize, self.vsize))
+    }
+
+    /// Starts the random numbers for a pixel afresh, so it comes out the same
+    /// however much of the image is rendered around it.
+    fn seed_pixel(&self, x: usize, y: usize, pass: usize) {
+        let index = (pass * self.vsize + y) * self.hsize + x;
+        random::seed(self.seed ^ Rng::new(index as u64).next_u64());
+    }
+
+    /// Fails for pixels off the image, and for pixels outside a fisheye's
+    /// image circle, which the lens doesn't see through.
     pub fn ray_for_pixel(&self, x: usize, y: usize) -> Result<Ray, PixelOutOfBoundsError> {
         if x > self.hsize || y > self.vsize {
             return Err(PixelOutOfBoundsError);
         }
 
-        let xoffset = (x as f64 + 0.5) * self.pixel_size;
-        let yoffset = (y as f64 + 0.5) * self.pixel_size;
+        self.ray_for_position(x as f64 + 0.5, y as f64 + 0.5)
+            .ok_or(PixelOutOfBoundsError)
+    }
+
+    /// Like `ray_for_pixel`, but `x` and `y` can land anywhere within a pixel.
+    fn ray_for_position(&self, x: f64, y: f64) -> Option<Ray> {
+        // Work in camera space, where the camera looks down -z and +x is to
+        // the left of the image
+        let (origin, direction) = match self.projection {
+            Projection::Perspective => self.perspective_ray(x, y),
+            Projection::Orthographic { .. } => {
+                let (world_x, world_y) = self.on_image_plane(x, y);
+                (
+                    Point::new(world_x, world_y, 0.0),
+                    Vector::new(0.0, 0.0, -1.0),
+                )
+            }
+            Projection::Equirectangular => {
+                let longitude = (x / self.hsize as f64 - 0.5) * 2.0 * PI;
+                let latitude = (0.5 - y / self.vsize as f64) * PI;
+                let direction = Vector::new(
+                    -longitude.sin() * latitude.cos(),
+                    latitude.sin(),
+                    -longitude.cos() * latitude.cos(),
+                );
+                (Point::new(0.0, 0.0, 0.0), direction)
+            }
+            Projection::Fisheye { field_of_view } => {
+                let radius = self.hsize.min(self.vsize) as f64 / 2.0;
+                let dx = (x - self.hsize as f64 / 2.0) / radius;
+                let dy = (self.vsize as f64 / 2.0 - y) / radius;
+                let distance = (dx * dx + dy * dy).sqrt();
+                // Past the edge of the circle the angle would overshoot the
+                // field of view, and eventually wrap round behind the camera
+                if distance > 1.0 {
+                    return None;
+                }
+                let angle = distance * field_of_view / 2.0;
+                let direction = if distance == 0.0 {
+                    Vector::new(0.0, 0.0, -1.0)
+                } else {
+                    Vector::new(
+                        -dx / distance * angle.sin(),
+                        dy / distance * angle.sin(),
+                        -angle.cos(),
+                    )
+                };
+                (Point::new(0.0, 0.0, 0.0), direction)
+            }
+        };
+
+        let origin = &self.inverse * origin;
+        let direction = (&self.inverse * direction).normalize();
+        Some(Ray::new(origin, direction))
+    }
 
-        let world_x = self.half_width - xoffset;
-        let world_y = self.half_height - yoffset;
+    /// Where `x` and `y` land on the plane one unit in front of the camera.
+    fn on_image_plane(&self, x: f64, y: f64) -> (f64, f64) {
+        let xoffset = x * self.pixel_size;
+        let yoffset = y * self.pixel_size;
+        (self.half_width - xoffset, self.half_height - yoffset)
+    }
 
-        let pixel = &self.inverse * Point::new(world_x, world_y, -1.0);
-        let origin = &self.inverse * Point::new(0.0, 0.0, 0.0);
-        let direction = (pixel - origin).normalize();
+    fn perspective_ray(&self, x: f64, y: f64) -> (Point, Vector) {
+        let (world_x, world_y) = self.on_image_plane(x, y);
+        if self.aperture > 0.0 {
+            // Rays from anywhere on the lens meet again on the focal plane
+            let focus = Point::new(
+                world_x * self.focal_distance,
+                world_y * self.focal_distance,
+                -self.focal_distance,
+            );
+            let (lens_x, lens_y) = in_unit_disk();
+            let lens = Point::new(lens_x * self.aperture, lens_y * self.aperture, 0.0);
+            return (lens, focus - lens);
+        }
 
-        Ok(Ray::new(origin, direction))
+        (
+            Point::new(0.0, 0.0, 0.0),
+            Vector::new(world_x, world_y, -1.0),
+        )
     }
 
     pub fn set_transform(&mut self, transform: Transform) -> Result<(), NoInverseError> {
@@ -76,17 +357,241 @@ impl Camera {
     }
 
     pub fn render(&self, world: &World) -> Canvas {
-        let mut image = Canvas::new(self.hsize, self.vsize);
+        self.render_with_progress(world, &CancellationToken::new(), |_| {})
+            .expect("render cancelled")
+    }
+
+    /// Renders like [`Camera::render`], calling `on_progress` after each row
+    /// and giving up between rows once `cancel` is cancelled.
+    pub fn render_with_progress<F>(
+        &self,
+        world: &World,
+        cancel: &CancellationToken,
+        mut on_progress: F,
+    ) -> Result<Canvas, CancelledError>
+    where
+        F: FnMut(&Progress),
+    {
+        let start = Instant::now();
+        let region = self.region();
+        let mut image = Canvas::new(region.width, region.height);
+
+        for row in 0..region.height {
+            if cancel.is_cancelled() {
+                return Err(CancelledError);
+            }
+            for column in 0..region.width {
+                let (x, y) = (region.x + column, region.y + row);
+                self.seed_pixel(x, y, 0);
+                let color = self.color_for_pixel(world, x, y);
+                image
+                    .write_pixel(column, row, color)
+                    .expect("pixel out of bounds");
+            }
+            on_progress(&Progress {
+                rows_done: row + 1,
+                rows: region.height,
+                elapsed: start.elapsed(),
+            });
+        }
+
+        Ok(image)
+    }
+
+    /// Renders in passes, each doubling the samples taken per pixel until
+    /// `samples_per_pixel` is reached, and hands the image so far to
+    /// `on_pass` along with how many samples each pixel has had. Returning
+    /// `ControlFlow::Break` from `on_pass` stops early with that image.
+    ///
+    /// Calls `on_progress` after each row of each pass, counting a row once
+    /// per sample its pixels take so the progress covers the whole render.
+    /// Gives up between rows once `cancel` is cancelled, keeping the last
+    /// finished pass, and only fails if that happens before the first pass is
+    /// done.
+    pub fn render_progressive<F, G>(
+        &self,
+        world: &World,
+        cancel: &CancellationToken,
+        mut on_progress: F,
+        mut on_pass: G,
+    ) -> Result<Canvas, CancelledError>
+    where
+        F: FnMut(&Progress),
+        G: FnMut(&Canvas, usize) -> ControlFlow<()>,
+    {
+        let start = Instant::now();
+        let samples = self.samples_per_pixel.max(1);
+        let region = self.region();
+        let mut totals = vec![Color::new(0.0, 0.0, 0.0); region.width * region.height];
+        let mut image = Canvas::new(region.width, region.height);
+
+        let mut done = 0;
+        for pass_number in 0.. {
+            let pass = done.max(1).min(samples - done);
+            for row in 0..region.height {
+                if cancel.is_cancelled() {
+                    return if done == 0 {
+                        Err(CancelledError)
+                    } else {
+                        Ok(image)
+                    };
+                }
+                for column in 0..region.width {
+                    let (x, y) = (region.x + column, region.y + row);
+                    self.seed_pixel(x, y, pass_number);
+                    let total = &mut totals[row * region.width + column];
+                    for _ in 0..pass {
+                        *total = *total + self.sample_pixel(world, x, y, samples > 1);
+                    }
+                }
+                on_progress(&Progress {
+                    rows_done: region.height * done + (row + 1) * pass,
+                    rows: region.height * samples,
+                    elapsed: start.elapsed(),
+                });
+            }
+            done += pass;
+
+            for row in 0..region.height {
+                for column in 0..region.width {
+                    let color = totals[row * region.width + column] * (1.0 / done as f64);
+                    image
+                        .write_pixel(column, row, color)
+                        .expect("pixel out of bounds");
+                }
+            }
+            if on_pass(&image, done).is_break() || done == samples {
+                break;
+            }
+        }
+
+        Ok(image)
+    }
+
+    fn color_for_pixel(&self, world: &World, x: usize, y: usize) -> Color {
+        let samples = self.samples_per_pixel.max(1);
+        let mut total = Color::new(0.0, 0.0, 0.0);
+        for _ in 0..samples {
+            total = total + self.sample_pixel(world, x, y, samples > 1);
+        }
+        total * (1.0 / samples as f64)
+    }
 
-        for y in 0..self.vsize {
-            for x in 0..self.hsize {
-                let ray = self.ray_for_pixel(x, y).expect("pixel out of bounds");
-                let color = world.color_from(&ray, RECURSION_DEPTH);
-                image.write_pixel(x, y, color).expect("pixel out of bounds");
+    /// The colour seen along one ray through the pixel, at a random spot in
+    /// it if `jitter` is set and through its centre otherwise.
+    fn sample_pixel(&self, world: &World, x: usize, y: usize, jitter: bool) -> Color {
+        let (dx, dy) = if jitter {
+            (random(), random())
+        } else {
+            (0.5, 0.5)
+        };
+        let time = if self.shutter_close > self.shutter_open {
+            self.shutter_open + random() * (self.shutter_close - self.shutter_open)
+        } else {
+            self.shutter_open
+        };
+        // Nothing is seen outside a fisheye's image circle
+        let Some(ray) = self.ray_for_position(x as f64 + dx, y as f64 + dy) else {
+            return BLACK;
+        };
+        let ray = ray.with_time(time);
+        match self.integrator {
+            Integrator::Whitted => {
+                let depth = self.max_depth.unwrap_or(RECURSION_DEPTH);
+                world.color_from(&ray, RayBudget::new(depth, self.min_contribution))
+            }
+            Integrator::PathTracing => {
+                world.path_traced_color(&ray, self.max_depth.unwrap_or(PATH_DEPTH))
             }
         }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum Lens {
+    FocalLength(f64),
+    VerticalFieldOfView(f64),
+}
+
+/// Sets a camera up the way a photographer would: where it stands, what it's
+/// pointed at, and the lens on it.
+///
+/// Unless told otherwise it stands at the origin looking down -z, with a 50mm
+/// lens on a full-frame sensor.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CameraBuilder {
+    hsize: usize,
+    vsize: usize,
+    position: Point,
+    target: Point,
+    up: Vector,
+    sensor_size: (f64, f64),
+    lens: Lens,
+}
+
+impl CameraBuilder {
+    pub fn new(hsize: usize, vsize: usize) -> Self {
+        CameraBuilder {
+            hsize,
+            vsize,
+            position: Point::new(0.0, 0.0, 0.0),
+            target: Point::new(0.0, 0.0, -1.0),
+            up: Vector::new(0.0, 1.0, 0.0),
+            sensor_size: FULL_FRAME,
+            lens: Lens::FocalLength(DEFAULT_FOCAL_LENGTH),
+        }
+    }
+
+    pub fn position(self, position: Point) -> Self {
+        CameraBuilder { position, ..self }
+    }
+
+    pub fn target(self, target: Point) -> Self {
+        CameraBuilder { target, ..self }
+    }
+
+    pub fn up(self, up: Vector) -> Self {
+        CameraBuilder { up, ..self }
+    }
+
+    /// Width and height in millimetres. The height is matched to the image.
+    pub fn sensor_size(self, width: f64, height: f64) -> Self {
+        CameraBuilder {
+            sensor_size: (width, height),
+            ..self
+        }
+    }
+
+    /// In millimetres, replacing any vertical field of view.
+    pub fn focal_length(self, focal_length: f64) -> Self {
+        CameraBuilder {
+            lens: Lens::FocalLength(focal_length),
+            ..self
+        }
+    }
+
+    /// In radians, replacing any focal length.
+    pub fn vertical_field_of_view(self, field_of_view: f64) -> Self {
+        CameraBuilder {
+            lens: Lens::VerticalFieldOfView(field_of_view),
+            ..self
+        }
+    }
+
+    /// Fails if the camera is looking straight along `up`.
+    pub fn build(self) -> Result<Camera, NoInverseError> {
+        let half_height = match self.lens {
+            Lens::FocalLength(focal_length) => self.sensor_size.1 / (2.0 * focal_length),
+            Lens::VerticalFieldOfView(field_of_view) => (field_of_view / 2.0).tan(),
+        };
+        // `Camera::new` wants the angle across the longer side
+        let aspect = self.hsize as f64 / self.vsize as f64;
+        let half_view = half_height * aspect.max(1.0);
 
-        image
+        let mut camera = Camera::new(self.hsize, self.vsize, 2.0 * half_view.atan());
+        camera.sensor_size = self.sensor_size;
+        camera.set_transform(view_transform(self.position, self.target, self.up))?;
+        Ok(camera)
     }
 }
 
@@ -95,8 +600,12 @@ mod test {
     use std::f64::consts::PI;
 
     use crate::{
-        canvas::Color,
-        transformations::{view_transform, Builder},
+        backgrounds::Background,
+        canvas::{Color, WHITE},
+        lights::PointLight,
+        patterns::UvImage,
+        shapes::{Plane, Shape, Sphere},
+        transformations::{translation, view_transform, Builder},
         world::default_world,
         Point, Vector, EQUALITY_EPSILON,
     };
@@ -113,6 +622,7 @@ mod test {
         assert_eq!(c.vsize, 120);
         assert_eq!(c.field_of_view, PI / 2.0);
         assert_eq!(c.transform, IDENTITY);
+        assert_eq!(c.focal_distance, DEFAULT_FOCAL_DISTANCE);
     }
 
     #[test]
@@ -127,6 +637,165 @@ mod test {
         assert!((c.pixel_size - 0.01).abs() < EQUALITY_EPSILON);
     }
 
+    #[test]
+    fn orthographic_pixel_size_follows_view_width() {
+        let mut c = Camera::new(200, 100, PI / 2.0);
+        c.set_projection(Projection::Orthographic { width: 10.0 });
+        assert_eq!(c.projection(), Projection::Orthographic { width: 10.0 });
+        assert!((c.pixel_size - 0.05).abs() < EQUALITY_EPSILON);
+        assert!((c.half_height - 2.5).abs() < EQUALITY_EPSILON);
+
+        c.set_projection(Projection::Perspective);
+        assert!((c.pixel_size - 0.01).abs() < EQUALITY_EPSILON);
+    }
+
+    #[test]
+    fn equirectangular_render_reproduces_background() {
+        let mut image = Canvas::new(8, 4);
+        for x in 0..8 {
+            for y in 0..4 {
+                let color = Color::new(x as f64 / 8.0, y as f64 / 4.0, 0.5);
+                image.write_pixel(x, y, color).unwrap();
+            }
+        }
+        let w = World {
+            background: Background::Equirectangular(UvImage::new(image.clone())),
+            ..World::default()
+        };
+        let mut c = Camera::new(8, 4, PI / 2.0);
+        c.set_projection(Projection::Equirectangular);
+        // The usual camera, looking along +z
+        c.set_transform(view_transform(
+            Point::new(0.0, 0.0, 0.0),
+            Point::new(0.0, 0.0, 1.0),
+            Vector::new(0.0, 1.0, 0.0),
+        ))
+        .unwrap();
+        assert_eq!(c.render(&w), image);
+    }
+
+    #[test]
+    fn fisheye_covers_hemisphere() {
+        let mut c = Camera::new(200, 100, PI / 2.0);
+        c.set_projection(Projection::Fisheye { field_of_view: PI });
+        let centre = c.ray_for_position(100.0, 50.0).unwrap();
+        assert_eq!(centre.direction, Vector::new(0.0, 0.0, -1.0));
+        let right = c.ray_for_position(150.0, 50.0).unwrap();
+        assert_eq!(right.direction, Vector::new(-1.0, 0.0, 0.0));
+        let top = c.ray_for_position(100.0, 0.0).unwrap();
+        assert_eq!(top.direction, Vector::new(0.0, 1.0, 0.0));
+        let halfway = c.ray_for_position(100.0, 75.0).unwrap();
+        assert_eq!(
+            halfway.direction,
+            Vector::new(0.0, -(2_f64.sqrt()) / 2.0, -(2_f64.sqrt()) / 2.0)
+        );
+    }
+
+    #[test]
+    fn fisheye_sees_nothing_outside_its_circle() {
+        let mut w = World::new();
+        w.background = Background::Solid(WHITE);
+        let mut c = Camera::new(200, 100, PI / 2.0);
+        c.set_projection(Projection::Fisheye { field_of_view: PI });
+        assert_eq!(c.ray_for_pixel(0, 0), Err(PixelOutOfBoundsError));
+        assert_eq!(c.ray_for_pixel(20, 50), Err(PixelOutOfBoundsError));
+        let image = c.render(&w);
+        assert_eq!(image.pixel_at(0, 0), Ok(BLACK));
+        assert_eq!(image.pixel_at(100, 0), Ok(WHITE));
+    }
+
+    #[test]
+    fn builder_defaults_to_normal_lens() {
+        let c = CameraBuilder::new(300, 200).build().unwrap();
+        assert_eq!((c.hsize(), c.vsize()), (300, 200));
+        assert_eq!(c.position(), Point::new(0.0, 0.0, 0.0));
+        assert_eq!(c.direction(), Vector::new(0.0, 0.0, -1.0));
+        assert_eq!(c.up(), Vector::new(0.0, 1.0, 0.0));
+        assert_eq!(c.sensor_size(), (36.0, 24.0));
+        assert!((c.focal_length() - 50.0).abs() < EQUALITY_EPSILON);
+        assert!(
+            (c.vertical_field_of_view() - 2.0 * (12.0_f64 / 50.0).atan()).abs() < EQUALITY_EPSILON
+        );
+    }
+
+    #[test]
+    fn builder_looks_from_position_at_target() {
+        let from = Point::new(1.0, 3.0, 2.0);
+        let to = Point::new(4.0, -2.0, 8.0);
+        let up = Vector::new(1.0, 1.0, 0.0);
+        let c = CameraBuilder::new(160, 120)
+            .position(from)
+            .target(to)
+            .up(up)
+            .build()
+            .unwrap();
+        assert_eq!(c.transform(), &view_transform(from, to, up));
+        assert_eq!(c.position(), from);
+        assert_eq!(c.direction(), (to - from).normalize());
+    }
+
+    #[test]
+    fn builder_matches_vertical_field_of_view_to_image() {
+        let wide = CameraBuilder::new(200, 100)
+            .vertical_field_of_view(PI / 2.0)
+            .build()
+            .unwrap();
+        assert!((wide.vertical_field_of_view() - PI / 2.0).abs() < EQUALITY_EPSILON);
+        assert!((wide.field_of_view() - 2.0 * 2.0_f64.atan()).abs() < EQUALITY_EPSILON);
+
+        let tall = CameraBuilder::new(100, 200)
+            .vertical_field_of_view(PI / 2.0)
+            .build()
+            .unwrap();
+        assert!((tall.field_of_view() - PI / 2.0).abs() < EQUALITY_EPSILON);
+    }
+
+    #[test]
+    fn builder_focal_length_depends_on_sensor() {
+        let c = CameraBuilder::new(300, 200)
+            .sensor_size(24.0, 16.0)
+            .focal_length(8.0)
+            .build()
+            .unwrap();
+        assert!((c.focal_length() - 8.0).abs() < EQUALITY_EPSILON);
+        assert!((c.vertical_field_of_view() - PI / 2.0).abs() < EQUALITY_EPSILON);
+    }
+
+    #[test]
+    fn builder_rejects_looking_along_up() {
+        let c = CameraBuilder::new(10, 10)
+            .target(Point::new(0.0, 5.0, 0.0))
+            .build();
+        assert_eq!(c, Err(NoInverseError));
+    }
+
+    #[test]
+    fn orthographic_rays_are_parallel() {
+        let mut c = Camera::new(201, 101, PI / 2.0);
+        c.set_projection(Projection::Orthographic { width: 20.1 });
+        c.set_transform(
+            Builder::new()
+                .translation(0.0, -2.0, 5.0)
+                .rotation_y(PI / 4.0)
+                .transform(),
+        )
+        .unwrap();
+        let centre = c.ray_for_pixel(100, 50).unwrap();
+        let corner = c.ray_for_pixel(0, 0).unwrap();
+        assert_eq!(centre.direction, corner.direction);
+        assert_eq!(
+            centre.direction,
+            Vector::new(2_f64.sqrt() / 2.0, 0.0, -(2_f64.sqrt()) / 2.0)
+        );
+        assert_eq!(centre.origin, Point::new(0.0, 2.0, -5.0));
+        let offset = corner.origin - centre.origin;
+        assert!((offset.y - 5.0).abs() < EQUALITY_EPSILON);
+        assert!(
+            (offset.magnitude() - (100.0_f64 * 100.0 + 50.0 * 50.0).sqrt() / 10.0).abs()
+                < EQUALITY_EPSILON
+        );
+    }
+
     #[test]
     fn ray_through_center() {
         let c = Camera::new(201, 101, PI / 2.0);
@@ -161,6 +830,317 @@ mod test {
         );
     }
 
+    #[test]
+    fn default_render_settings() {
+        let c = Camera::new(160, 120, PI / 2.0);
+        assert_eq!(c.integrator, Integrator::Whitted);
+        assert_eq!(c.samples_per_pixel, 1);
+        assert_eq!(c.max_depth, None);
+        assert_eq!(c.min_contribution, CONTRIBUTION_CUTOFF);
+        assert_eq!(c.aperture, 0.0);
+        assert_eq!((c.shutter_open, c.shutter_close), (0.0, 0.0));
+    }
+
+    #[test]
+    fn open_shutter_blurs_moving_shapes() {
+        let mut w = World::new();
+        w.light = PointLight::new(Point::new(0.0, 0.0, -10.0), Color::new(1.0, 1.0, 1.0));
+        let mut ball = Shape::new(Sphere);
+        ball.material.ambient = 1.0;
+        ball.material.diffuse = 0.0;
+        ball.material.specular = 0.0;
+        ball.set_motion(translation(4.0, 0.0, 0.0)).unwrap();
+        w.objects = vec![ball];
+        let mut c = Camera::new(1, 1, PI / 16.0);
+        c.set_transform(view_transform(
+            Point::new(0.0, 0.0, -5.0),
+            Point::new(0.0, 0.0, 0.0),
+            Vector::new(0.0, 1.0, 0.0),
+        ))
+        .unwrap();
+        c.samples_per_pixel = 64;
+
+        // At time 0 the ball sits right in front of the camera
+        let still = c.render(&w).pixel_at(0, 0).unwrap();
+        assert_eq!(still, Color::new(1.0, 1.0, 1.0));
+
+        // It's only in front for about the first quarter of the exposure
+        c.shutter_close = 1.0;
+        let blurred = c.render(&w).pixel_at(0, 0).unwrap();
+        assert!(blurred.red() > 0.1 && blurred.red() < 0.5);
+    }
+
+    #[test]
+    fn lens_rays_meet_on_focal_plane() {
+        let mut c = Camera::new(201, 101, PI / 2.0);
+        c.set_transform(translation(0.0, -2.0, 0.0)).unwrap();
+        let pinhole = c.ray_for_pixel(150, 50).unwrap();
+        let focus = pinhole.position(-4.0 / pinhole.direction.z);
+
+        c.aperture = 0.5;
+        c.focal_distance = 4.0;
+        random::seed(1);
+        for _ in 0..20 {
+            let r = c.ray_for_pixel(150, 50).unwrap();
+            let (x, y) = (r.origin.x, r.origin.y - 2.0);
+            assert!(x * x + y * y <= 0.25);
+            assert!(r.origin.z.abs() < EQUALITY_EPSILON);
+            assert_eq!(r.position((focus.z - r.origin.z) / r.direction.z), focus);
+        }
+    }
+
+    #[test]
+    fn max_depth_limits_reflections() {
+        let mut w = default_world();
+        let mut mirror = Shape::new(Plane);
+        mirror.material.reflective = 0.5;
+        mirror.set_transform(translation(0.0, -1.0, 0.0)).unwrap();
+        w.objects.push(mirror);
+        // The middle pixel sees the spheres reflected in the mirror
+        let mut c = Camera::new(11, 11, PI / 2.0);
+        c.set_transform(view_transform(
+            Point::new(0.0, 0.0, -3.0),
+            Point::new(0.0, -1.0, -2.0),
+            Vector::new(0.0, 1.0, 0.0),
+        ))
+        .unwrap();
+        let reflecting = c.render(&w);
+        c.max_depth = Some(0);
+        let flat = c.render(&w);
+        assert_ne!(reflecting.pixel_at(5, 5), flat.pixel_at(5, 5));
+    }
+
+    #[test]
+    fn path_traced_render_is_repeatable() {
+        let mut w = default_world();
+        w.background = Background::Solid(Color::new(0.5, 0.5, 0.5));
+        let mut c = Camera::new(5, 5, PI / 2.0);
+        c.set_transform(view_transform(
+            Point::new(0.0, 0.0, -5.0),
+            Point::new(0.0, 0.0, 0.0),
+            Vector::new(0.0, 1.0, 0.0),
+        ))
+        .unwrap();
+        c.integrator = Integrator::PathTracing;
+        c.samples_per_pixel = 4;
+        assert_eq!(c.render(&w), c.render(&w));
+        c.seed = 1;
+        let reseeded = c.render(&w);
+        c.seed = 0;
+        assert_ne!(c.render(&w), reseeded);
+    }
+
+    #[test]
+    fn progressive_render_doubles_samples_each_pass() {
+        let mut w = default_world();
+        w.background = Background::Solid(Color::new(0.5, 0.5, 0.5));
+        let mut c = Camera::new(5, 5, PI / 2.0);
+        c.integrator = Integrator::PathTracing;
+        c.samples_per_pixel = 6;
+        let mut passes = vec![];
+        c.render_progressive(
+            &w,
+            &CancellationToken::new(),
+            |_| {},
+            |image, samples| {
+                assert_eq!((image.width(), image.height()), (5, 5));
+                passes.push(samples);
+                ControlFlow::Continue(())
+            },
+        )
+        .unwrap();
+        assert_eq!(passes, vec![1, 2, 4, 6]);
+    }
+
+    #[test]
+    fn progressive_render_stops_when_told() {
+        let w = default_world();
+        let mut c = Camera::new(5, 5, PI / 2.0);
+        c.samples_per_pixel = 16;
+        let mut first_pass = None;
+        let image = c
+            .render_progressive(
+                &w,
+                &CancellationToken::new(),
+                |_| {},
+                |image, samples| {
+                    first_pass = Some(image.clone());
+                    assert_eq!(samples, 1);
+                    ControlFlow::Break(())
+                },
+            )
+            .unwrap();
+        assert_eq!(Some(image), first_pass);
+    }
+
+    #[test]
+    fn progressive_render_with_one_sample_matches_render() {
+        let w = default_world();
+        let c = Camera::new(11, 11, PI / 2.0);
+        let image = c
+            .render_progressive(
+                &w,
+                &CancellationToken::new(),
+                |_| {},
+                |_, _| ControlFlow::Continue(()),
+            )
+            .unwrap();
+        assert_eq!(image, c.render(&w));
+    }
+
+    #[test]
+    fn cancelled_progressive_render_keeps_last_pass() {
+        let w = default_world();
+        let mut c = Camera::new(4, 3, PI / 2.0);
+        c.samples_per_pixel = 16;
+        let cancel = CancellationToken::new();
+        let mut rows = vec![];
+        let mut last_pass = None;
+        let image = c
+            .render_progressive(
+                &w,
+                &cancel,
+                |progress| {
+                    assert_eq!(progress.rows, 3 * 16);
+                    rows.push(progress.rows_done);
+                    // Partway through the third pass
+                    if rows.len() == 7 {
+                        cancel.cancel();
+                    }
+                },
+                |image, _| {
+                    last_pass = Some(image.clone());
+                    ControlFlow::Continue(())
+                },
+            )
+            .unwrap();
+        assert_eq!(rows, vec![1, 2, 3, 4, 5, 6, 8]);
+        assert_eq!(Some(image), last_pass);
+    }
+
+    #[test]
+    fn progressive_render_reports_progress_for_the_whole_render() {
+        let w = default_world();
+        let mut c = Camera::new(4, 2, PI / 2.0);
+        c.samples_per_pixel = 4;
+        let mut rows = vec![];
+        c.render_progressive(
+            &w,
+            &CancellationToken::new(),
+            |progress| {
+                assert_eq!(progress.rows, 8);
+                rows.push(progress.rows_done);
+            },
+            |_, _| ControlFlow::Continue(()),
+        )
+        .unwrap();
+        assert_eq!(rows, vec![1, 2, 3, 4, 6, 8]);
+    }
+
+    #[test]
+    fn progressive_render_cancelled_in_first_pass_fails() {
+        let w = default_world();
+        let c = Camera::new(4, 3, PI / 2.0);
+        let cancel = CancellationToken::new();
+        cancel.cancel();
+        let result = c.render_progressive(&w, &cancel, |_| {}, |_, _| ControlFlow::Continue(()));
+        assert_eq!(result, Err(CancelledError));
+    }
+
+    #[test]
+    fn render_reports_each_row() {
+        let w = default_world();
+        let c = Camera::new(4, 3, PI / 2.0);
+        let mut rows = vec![];
+        let image = c
+            .render_with_progress(&w, &CancellationToken::new(), |progress| {
+                assert_eq!(progress.rows, 3);
+                rows.push(progress.rows_done);
+            })
+            .unwrap();
+        assert_eq!(rows, vec![1, 2, 3]);
+        assert_eq!(image, c.render(&w));
+    }
+
+    #[test]
+    fn cancelled_render_stops_between_rows() {
+        let w = default_world();
+        let c = Camera::new(4, 3, PI / 2.0);
+        let cancel = CancellationToken::new();
+        let mut rows = 0;
+        let result = c.render_with_progress(&w, &cancel, |_| {
+            rows += 1;
+            cancel.cancel();
+        });
+        assert_eq!(result, Err(CancelledError));
+        assert_eq!(rows, 1);
+    }
+
+    #[test]
+    fn crop_must_fit_in_image() {
+        let mut c = Camera::new(10, 5, PI / 2.0);
+        assert_eq!(
+            c.set_crop(Some(Crop::new(8, 0, 3, 1))),
+            Err(CropOutOfBoundsError)
+        );
+        assert_eq!(
+            c.set_crop(Some(Crop::new(0, 4, 1, 2))),
+            Err(CropOutOfBoundsError)
+        );
+        assert_eq!(
+            c.set_crop(Some(Crop::new(usize::MAX, 0, 2, 1))),
+            Err(CropOutOfBoundsError)
+        );
+        assert_eq!(c.crop(), None);
+        c.set_crop(Some(Crop::new(8, 3, 2, 2))).unwrap();
+        assert_eq!(c.crop(), Some(Crop::new(8, 3, 2, 2)));
+    }
+
+    #[test]
+    fn cropped_render_matches_full_render() {
+        let mut w = default_world();
+        w.background = Background::Solid(Color::new(0.5, 0.5, 0.5));
+        let mut c = Camera::new(9, 7, PI / 2.0);
+        c.set_transform(view_transform(
+            Point::new(0.0, 0.0, -5.0),
+            Point::new(0.0, 0.0, 0.0),
+            Vector::new(0.0, 1.0, 0.0),
+        ))
+        .unwrap();
+        c.integrator = Integrator::PathTracing;
+        c.samples_per_pixel = 3;
+        let full = c.render(&w);
+        let full_progressive = c
+            .render_progressive(
+                &w,
+                &CancellationToken::new(),
+                |_| {},
+                |_, _| ControlFlow::Continue(()),
+            )
+            .unwrap();
+
+        c.set_crop(Some(Crop::new(3, 2, 4, 3))).unwrap();
+        let cropped = c.render(&w);
+        let cropped_progressive = c
+            .render_progressive(
+                &w,
+                &CancellationToken::new(),
+                |_| {},
+                |_, _| ControlFlow::Continue(()),
+            )
+            .unwrap();
+        assert_eq!((cropped.width(), cropped.height()), (4, 3));
+        for y in 0..3 {
+            for x in 0..4 {
+                assert_eq!(cropped.pixel_at(x, y), full.pixel_at(x + 3, y + 2));
+                assert_eq!(
+                    cropped_progressive.pixel_at(x, y),
+                    full_progressive.pixel_at(x + 3, y + 2)
+                );
+            }
+        }
+    }
+
     #[test]
     fn render() {
         let w = default_world();
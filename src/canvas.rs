@@ -14,6 +14,18 @@ impl Color {
     pub const fn new(red: f64, green: f64, blue: f64) -> Self {
         Color { red, green, blue }
     }
+
+    pub fn red(&self) -> f64 {
+        self.red
+    }
+
+    pub fn green(&self) -> f64 {
+        self.green
+    }
+
+    pub fn blue(&self) -> f64 {
+        self.blue
+    }
 }
 
 impl PartialEq for Color {
@@ -90,6 +102,9 @@ pub struct Canvas {
 #[derive(Default, Debug, Copy, Clone, PartialEq, Eq)]
 pub struct PixelOutOfBoundsError;
 
+#[derive(Default, Debug, Copy, Clone, PartialEq, Eq)]
+pub struct ParsePpmError;
+
 impl Canvas {
     pub fn new(width: usize, height: usize) -> Canvas {
         Canvas {
@@ -129,6 +144,49 @@ impl Canvas {
         }
     }
 
+    /// Reads a plain (`P3`) PPM image, such as one written by [`Canvas::to_ppm`].
+    pub fn from_ppm(ppm: &str) -> Result<Canvas, ParsePpmError> {
+        let mut tokens = ppm
+            .lines()
+            .map(|line| line.split('#').next().unwrap_or_default())
+            .flat_map(str::split_whitespace);
+
+        if tokens.next() != Some("P3") {
+            return Err(ParsePpmError);
+        }
+        let mut next_number = || -> Result<usize, ParsePpmError> {
+            tokens
+                .next()
+                .ok_or(ParsePpmError)?
+                .parse()
+                .map_err(|_| ParsePpmError)
+        };
+        let width = next_number()?;
+        let height = next_number()?;
+        let scale = next_number()?;
+        if scale == 0 {
+            return Err(ParsePpmError);
+        }
+
+        // Check the header against the data before allocating anything
+        let samples = tokens.collect::<Vec<_>>();
+        let pixel_count = width.checked_mul(height).ok_or(ParsePpmError)?;
+        if pixel_count.checked_mul(3).ok_or(ParsePpmError)? > samples.len() {
+            return Err(ParsePpmError);
+        }
+
+        let mut canvas = Canvas::new(width, height);
+        for (pixel, rgb) in canvas.pixels.iter_mut().zip(samples.chunks_exact(3)) {
+            let channel = |i: usize| -> Result<f64, ParsePpmError> {
+                let value: usize = rgb[i].parse().map_err(|_| ParsePpmError)?;
+                Ok(value as f64 / scale as f64)
+            };
+            *pixel = Color::new(channel(0)?, channel(1)?, channel(2)?);
+        }
+
+        Ok(canvas)
+    }
+
     pub fn to_ppm(&self) -> String {
         format!(
             "P3\n\
@@ -303,4 +361,82 @@ mod tests {
         let ppm = c.to_ppm();
         assert!(ppm.ends_with('\n'));
     }
+
+    #[test]
+    fn read_ppm_with_wrong_magic_number() {
+        let ppm = "P32\n\
+            1 1\n\
+            255\n\
+            0 0 0\n";
+        assert_eq!(Canvas::from_ppm(ppm), Err(ParsePpmError));
+    }
+
+    #[test]
+    fn read_ppm_pixel_data() {
+        let ppm = "P3\n\
+            4 3\n\
+            255\n\
+            255 127 0  0 127 255  127 255 0  255 255 255\n\
+            0 0 0  255 0 0  0 255 0  0 0 255\n\
+            255 255 0  0 255 255  255 0 255  127 127 127\n";
+        let c = Canvas::from_ppm(ppm).unwrap();
+        assert_eq!(c.width(), 4);
+        assert_eq!(c.height(), 3);
+        assert_eq!(c.pixel_at(0, 0), Ok(Color::new(1.0, 0.49804, 0.0)));
+        assert_eq!(c.pixel_at(1, 0), Ok(Color::new(0.0, 0.49804, 1.0)));
+        assert_eq!(c.pixel_at(3, 1), Ok(Color::new(0.0, 0.0, 1.0)));
+        assert_eq!(c.pixel_at(3, 2), Ok(Color::new(0.49804, 0.49804, 0.49804)));
+    }
+
+    #[test]
+    fn read_ppm_ignores_comments() {
+        let ppm = "P3\n\
+            # this is a comment\n\
+            2 1\n\
+            # this, too\n\
+            255\n\
+            # another comment\n\
+            255 255 255\n\
+            # oh, no, comments in the pixel data!\n\
+            255 0 255\n";
+        let c = Canvas::from_ppm(ppm).unwrap();
+        assert_eq!(c.pixel_at(1, 0), Ok(Color::new(1.0, 0.0, 1.0)));
+    }
+
+    #[test]
+    fn read_ppm_with_different_scale() {
+        let ppm = "P3\n\
+            2 2\n\
+            100\n\
+            100 100 100  50 50 50\n\
+            75 50 25  0 0 0\n";
+        let c = Canvas::from_ppm(ppm).unwrap();
+        assert_eq!(c.pixel_at(0, 1), Ok(Color::new(0.75, 0.5, 0.25)));
+    }
+
+    #[test]
+    fn read_truncated_ppm() {
+        let ppm = "P3\n\
+            2 1\n\
+            255\n\
+            255 255 255\n";
+        assert_eq!(Canvas::from_ppm(ppm), Err(ParsePpmError));
+    }
+
+    #[test]
+    fn read_ppm_with_zero_scale() {
+        let ppm = "P3\n\
+            1 1\n\
+            0\n\
+            0 0 0\n";
+        assert_eq!(Canvas::from_ppm(ppm), Err(ParsePpmError));
+    }
+
+    #[test]
+    fn read_ppm_with_size_beyond_its_data() {
+        let huge = format!("P3\n{} {}\n255\n0 0 0\n", usize::MAX / 2, 3);
+        assert_eq!(Canvas::from_ppm(&huge), Err(ParsePpmError));
+        let large = "P3\n100000 100000\n255\n0 0 0\n";
+        assert_eq!(Canvas::from_ppm(large), Err(ParsePpmError));
+    }
 }
@@ -76,6 +76,10 @@ pub struct HitInfo<'object> {
     pub n1: f64,
     pub n2: f64,
     pub under_point: Point,
+    /// The innermost object the ray travelled through to reach the hit.
+    pub medium: Option<&'object Shape>,
+    pub wavelength: Option<f64>,
+    pub time: f64,
 }
 
 #[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
@@ -92,14 +96,21 @@ impl<'object> HitInfo<'object> {
         let object = intersection.object;
         let point = ray.position(t);
         let eyev = -ray.direction;
-        let naive_normal = object.normal_at(point);
+        let naive_normal = object.normal_at_time(point, ray.time);
         let inside = Vector::dot(naive_normal, eyev) < 0.0;
-        let normal = if inside { -naive_normal } else { naive_normal };
-        let over_point = point + normal * SHADOW_EPSILON;
+        let surface_normal = if inside { -naive_normal } else { naive_normal };
+        let normal = match &object.material.bump {
+            Some(bump) => bump.perturb(object, point, surface_normal, ray.time),
+            None => surface_normal,
+        };
+        // Bumps only change shading; offsetting along a tilted normal could
+        // leave the point barely off the actual surface
+        let over_point = point + surface_normal * SHADOW_EPSILON;
         let reflectv = ray.direction.reflect(normal);
 
         let mut n1 = 1.0;
         let mut n2 = 1.0;
+        let mut medium = None;
         // Assumes intersections is sorted by t value of the intersection
         let mut first_index_by_object = HashMap::<*const Shape, usize>::new();
         let mut containers = BTreeMap::<usize, &Shape>::new();
@@ -107,7 +118,8 @@ impl<'object> HitInfo<'object> {
             let object = intersections[index].object;
             if index == hit_index {
                 if let Some((_, object)) = containers.last_key_value() {
-                    n1 = object.material.refractive_index;
+                    n1 = object.material.refractive_index_at(ray.wavelength);
+                    medium = Some(*object);
                 }
             }
 
@@ -120,12 +132,12 @@ impl<'object> HitInfo<'object> {
 
             if index == hit_index {
                 if let Some((_, object)) = containers.last_key_value() {
-                    n2 = object.material.refractive_index;
+                    n2 = object.material.refractive_index_at(ray.wavelength);
                 }
                 break;
             }
         }
-        let under_point = point - normal * SHADOW_EPSILON;
+        let under_point = point - surface_normal * SHADOW_EPSILON;
 
         Some(HitInfo {
             t,
@@ -139,13 +151,27 @@ impl<'object> HitInfo<'object> {
             n1,
             n2,
             under_point,
+            medium,
+            wavelength: ray.wavelength,
+            time: ray.time,
         })
     }
+
+    /// A ray leaving the hit, carrying on with the incoming ray's wavelength
+    /// and time.
+    pub fn spawn_ray(&self, origin: Point, direction: Vector) -> Ray {
+        Ray::new(origin, direction)
+            .with_wavelength(self.wavelength)
+            .with_time(self.time)
+    }
 }
 
 #[cfg(test)]
 mod test {
     use crate::{
+        bumps::Bump,
+        canvas::{BLACK, WHITE},
+        patterns::{Gradient, Pattern},
         rays::Ray,
         shapes::{Plane, Sphere},
         transformations::{scaling, translation},
@@ -317,6 +343,71 @@ mod test {
         }
     }
 
+    #[test]
+    fn n1_and_n2_follow_wavelength() {
+        let mut a = Sphere::new_glass();
+        a.material.dispersion = 0.01;
+        let r = Ray::new(Point::new(0.0, 0.0, -4.0), Vector::new(0.0, 0.0, 1.0));
+        let xs = Intersections::new(vec![Intersection::new(3.0, &a), Intersection::new(5.0, &a)]);
+
+        let white = HitInfo::prepare(&xs, &r, 0).unwrap();
+        assert_eq!((white.n1, white.n2), (1.0, 1.5));
+        assert_eq!(white.wavelength, None);
+
+        let blue = r.with_wavelength(Some(0.45));
+        let hit_info = HitInfo::prepare(&xs, &blue, 1).unwrap();
+        assert_eq!(hit_info.n1, a.material.refractive_index_at(Some(0.45)));
+        assert_eq!(hit_info.n2, 1.0);
+        let spawned = hit_info.spawn_ray(hit_info.over_point, hit_info.reflectv);
+        assert_eq!(spawned.wavelength, Some(0.45));
+    }
+
+    #[test]
+    fn medium_is_innermost_container() {
+        let a = Sphere::new_glass();
+        let mut b = Sphere::new_glass();
+        b.set_transform(scaling(0.5, 0.5, 0.5)).unwrap();
+        let r = Ray::new(Point::new(0.0, 0.0, -4.0), Vector::new(0.0, 0.0, 1.0));
+        let xs = Intersections::new(vec![
+            Intersection::new(3.0, &a),
+            Intersection::new(3.5, &b),
+            Intersection::new(4.5, &b),
+            Intersection::new(5.0, &a),
+        ]);
+
+        let media = (0..4)
+            .map(|index| HitInfo::prepare(&xs, &r, index).unwrap().medium)
+            .collect::<Vec<_>>();
+        assert_eq!(media, vec![None, Some(&a), Some(&b), Some(&a)]);
+    }
+
+    #[test]
+    fn bump_perturbs_normal_and_reflection() {
+        let mut shape = Shape::new(Plane);
+        shape.material.bump = Some(Bump::new(Pattern::new(Gradient::new(BLACK, WHITE)), 1.0));
+        let r = Ray::new(Point::new(0.5, 1.0, 0.0), Vector::new(0.0, -1.0, 0.0));
+        let xs = Intersections::new(vec![Intersection::new(1.0, &shape)]);
+        let hit_info = HitInfo::prepare(&xs, &r, 0).unwrap();
+        assert_eq!(
+            hit_info.normal,
+            Vector::new(-(2_f64.sqrt()) / 2.0, 2_f64.sqrt() / 2.0, 0.0)
+        );
+        assert_eq!(hit_info.reflectv, Vector::new(-1.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn bump_leaves_offset_points_along_the_surface_normal() {
+        let mut shape = Shape::new(Plane);
+        shape.material.bump = Some(Bump::new(Pattern::new(Gradient::new(BLACK, WHITE)), 10.0));
+        let r = Ray::new(Point::new(0.5, 1.0, 0.0), Vector::new(0.0, -1.0, 0.0));
+        let xs = Intersections::new(vec![Intersection::new(1.0, &shape)]);
+        let hit_info = HitInfo::prepare(&xs, &r, 0).unwrap();
+        assert!(hit_info.normal.x < -0.9);
+        let up = Vector::new(0.0, 1.0, 0.0);
+        assert_eq!((hit_info.over_point - hit_info.point).normalize(), up);
+        assert_eq!((hit_info.point - hit_info.under_point).normalize(), up);
+    }
+
     #[test]
     fn under_point() {
         let r = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
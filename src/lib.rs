@@ -1,10 +1,18 @@
+pub mod animation;
+pub mod backgrounds;
+pub mod bumps;
 pub mod camera;
 pub mod canvas;
 pub mod intersections;
 pub mod lights;
 pub mod materials;
 pub mod matrices;
+pub mod media;
+pub mod noise;
 pub mod patterns;
+pub mod progress;
+pub mod quaternions;
+pub mod random;
 pub mod rays;
 pub mod shapes;
 pub mod transformations;
@@ -1,4 +1,28 @@
-use crate::{canvas::Color, lights::PointLight, patterns::Pattern, shapes::Shape, Point, Vector};
+use std::f64::consts::PI;
+
+use crate::{
+    bumps::Bump,
+    canvas::{Color, WHITE},
+    lights::PointLight,
+    patterns::Pattern,
+    random::{cosine_weighted_hemisphere, orthonormal_basis, random},
+    shapes::Shape,
+    Point, Vector,
+};
+
+// The sodium D line, where refractive indices are conventionally quoted
+const REFERENCE_WAVELENGTH: f64 = 0.5893;
+
+/// Which reflectance model `lighting` uses for the diffuse and specular terms.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub enum Shading {
+    /// Classic Phong, using `diffuse`, `specular` and `shininess`.
+    #[default]
+    Phong,
+    /// GGX/Cook-Torrance, using `color` as the base colour along with
+    /// `roughness` and `metallic`.
+    Microfacet,
+}
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct Material {
@@ -11,12 +35,73 @@ pub struct Material {
     pub reflective: f64,
     pub transparaency: f64,
     pub refractive_index: f64,
+    /// Cauchy's `B` coefficient in µm², so the index at wavelength `λ` is
+    /// `refractive_index + dispersion * (1/λ² - 1/0.5893²)`.
+    pub dispersion: f64,
+    /// How much of each channel is absorbed per unit of distance travelled
+    /// inside the object.
+    pub absorption: Color,
+    pub bump: Option<Bump>,
+    pub shading: Shading,
+    /// Spreads highlights under `Shading::Microfacet`.
+    pub roughness: f64,
+    /// Blurs reflection and refraction in any shading model, by up to this
+    /// much of a unit vector. Off at zero.
+    pub glossiness: f64,
+    /// Rays averaged for each glossy reflection or refraction.
+    pub glossy_samples: usize,
+    pub metallic: f64,
+    pub emissive: Color,
+    /// Turn off for things like a glowing bulb around the light, which
+    /// shouldn't block it.
+    pub casts_shadow: bool,
 }
 
 impl Material {
     pub fn new() -> Self {
         Material::default()
     }
+
+    pub fn is_emissive(&self) -> bool {
+        self.emissive != Color::new(0.0, 0.0, 0.0)
+    }
+
+    /// Whether transparent parts of this material split white light apart.
+    pub fn is_dispersive(&self) -> bool {
+        self.dispersion != 0.0 && self.transparaency > 0.0
+    }
+
+    /// The refractive index for light of `wavelength` micrometres, or the
+    /// nominal index for white light.
+    pub fn refractive_index_at(&self, wavelength: Option<f64>) -> f64 {
+        match wavelength {
+            Some(wavelength) => {
+                self.refractive_index
+                    + self.dispersion
+                        * (1.0 / (wavelength * wavelength)
+                            - 1.0 / (REFERENCE_WAVELENGTH * REFERENCE_WAVELENGTH))
+            }
+            None => self.refractive_index,
+        }
+    }
+
+    /// The fraction of light left after travelling `distance` inside the
+    /// object, following the Beer-Lambert law.
+    pub fn transmittance(&self, distance: f64) -> Color {
+        Color::new(
+            (-self.absorption.red() * distance).exp(),
+            (-self.absorption.green() * distance).exp(),
+            (-self.absorption.blue() * distance).exp(),
+        )
+    }
+
+    /// The surface colour at `point`, from the pattern if there is one, on
+    /// `object` as it is at `time`.
+    pub fn color_at(&self, object: &Shape, point: Point, time: f64) -> Color {
+        self.pattern
+            .as_ref()
+            .map_or(self.color, |pattern| pattern.at_shape(object, point, time))
+    }
 }
 
 impl Default for Material {
@@ -31,28 +116,53 @@ impl Default for Material {
             reflective: 0.0,
             transparaency: 0.0,
             refractive_index: 1.0,
+            dispersion: 0.0,
+            absorption: Color::new(0.0, 0.0, 0.0),
+            bump: None,
+            shading: Shading::Phong,
+            roughness: 0.0,
+            glossiness: 0.0,
+            glossy_samples: 1,
+            metallic: 0.0,
+            emissive: Color::new(0.0, 0.0, 0.0),
+            casts_shadow: true,
         }
     }
 }
 
+/// How much light gets to a point being lit.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Visibility {
+    /// Whether the light is blocked.
+    pub in_shadow: bool,
+    /// The fraction of ambient light not blocked by nearby objects.
+    pub ambient: f64,
+}
+
+/// A point being lit, in world space, and the time the ray reached it, so
+/// patterns on moving shapes are looked up where the shape was.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SurfacePoint {
+    pub point: Point,
+    pub time: f64,
+}
+
 pub fn lighting(
     material: &Material,
     object: &Shape,
     light: &PointLight,
-    point: Point,
+    surface_point: SurfacePoint,
     eyev: Vector,
     normal: Vector,
-    in_shadow: bool,
+    visibility: Visibility,
 ) -> Color {
-    let color = material
-        .pattern
-        .as_ref()
-        .map_or(material.color, |pattern| pattern.at_shape(object, point));
+    let SurfacePoint { point, time } = surface_point;
+    let color = material.color_at(object, point, time);
     let effective_color = color * light.intensity;
     let lightv = (light.position - point).normalize();
 
-    let ambient = effective_color * material.ambient;
-    if in_shadow {
+    let ambient = effective_color * material.ambient * visibility.ambient;
+    if visibility.in_shadow {
         return ambient;
     }
 
@@ -62,6 +172,10 @@ pub fn lighting(
     if light_dot_normal < 0.0 {
         diffuse = Color::default();
         specular = Color::default();
+    } else if material.shading == Shading::Microfacet {
+        let (diffuse_brdf, specular_brdf) = microfacet(material, color, lightv, eyev, normal);
+        diffuse = diffuse_brdf * light.intensity * light_dot_normal;
+        specular = specular_brdf * light.intensity * light_dot_normal;
     } else {
         diffuse = effective_color * material.diffuse * light_dot_normal;
 
@@ -78,6 +192,101 @@ pub fn lighting(
     ambient + diffuse + specular
 }
 
+// Keeps the GGX distribution finite for perfectly smooth materials
+const MIN_ALPHA: f64 = 0.002;
+
+/// Returns the diffuse and specular parts of the Cook-Torrance BRDF.
+///
+/// Both are scaled up by pi, so that a white Lambertian surface lit head-on by
+/// a white light comes out white, matching the brightness of `Shading::Phong`.
+pub(crate) fn microfacet(
+    material: &Material,
+    base_color: Color,
+    lightv: Vector,
+    eyev: Vector,
+    normal: Vector,
+) -> (Color, Color) {
+    let halfway = (lightv + eyev).normalize();
+    let n_dot_l = Vector::dot(normal, lightv).max(0.0);
+    let n_dot_v = Vector::dot(normal, eyev).max(0.0001);
+    let n_dot_h = Vector::dot(normal, halfway).max(0.0);
+    let v_dot_h = Vector::dot(eyev, halfway).max(0.0);
+
+    let alpha = ggx_alpha(material);
+    let distribution = ggx_distribution(n_dot_h, alpha);
+
+    // Smith geometry term with Schlick-GGX masking for each direction
+    let k = alpha / 2.0;
+    let masking = |n_dot_x: f64| n_dot_x / (n_dot_x * (1.0 - k) + k);
+    let geometry = masking(n_dot_l) * masking(n_dot_v);
+
+    // Schlick's Fresnel, with metals tinting their reflections
+    let f0 =
+        Color::new(0.04, 0.04, 0.04) * (1.0 - material.metallic) + base_color * material.metallic;
+    let fresnel = f0 + (WHITE - f0) * (1.0 - v_dot_h).powi(5);
+
+    let specular = fresnel * (PI * distribution * geometry / (4.0 * n_dot_l.max(0.0001) * n_dot_v));
+    // Lambert's 1/pi cancels out with the scaling
+    let diffuse = (WHITE - fresnel) * base_color * (1.0 - material.metallic);
+
+    (diffuse, specular)
+}
+
+fn ggx_alpha(material: &Material) -> f64 {
+    (material.roughness * material.roughness).max(MIN_ALPHA)
+}
+
+/// The GGX normal distribution.
+fn ggx_distribution(n_dot_h: f64, alpha: f64) -> f64 {
+    let alpha2 = alpha * alpha;
+    let denominator = n_dot_h * n_dot_h * (alpha2 - 1.0) + 1.0;
+    alpha2 / (PI * denominator * denominator)
+}
+
+/// Picks a direction for a path to carry on in from a `Shading::Microfacet`
+/// surface, from either the diffuse or the specular lobe. Returns it along
+/// with what the path's throughput is multiplied by, or `None` if the
+/// direction would go into the surface.
+pub fn sample_microfacet(
+    material: &Material,
+    base_color: Color,
+    eyev: Vector,
+    normal: Vector,
+) -> Option<(Vector, Color)> {
+    // Metals have no diffuse lobe to sample
+    let specular_chance = 0.5 + 0.5 * material.metallic;
+    if random() >= specular_chance {
+        let lightv = cosine_weighted_hemisphere(normal);
+        let (diffuse, _) = microfacet(material, base_color, lightv, eyev, normal);
+        return Some((lightv, diffuse * (1.0 / (1.0 - specular_chance))));
+    }
+
+    // Pick a microfacet normal in proportion to how many face that way
+    let alpha = ggx_alpha(material);
+    let u = random();
+    let cos_theta = ((1.0 - u) / (1.0 + (alpha * alpha - 1.0) * u)).sqrt();
+    let sin_theta = (1.0 - cos_theta * cos_theta).max(0.0).sqrt();
+    let phi = 2.0 * PI * random();
+    let (tangent, bitangent) = orthonormal_basis(normal);
+    let halfway = (tangent * (sin_theta * phi.cos())
+        + bitangent * (sin_theta * phi.sin())
+        + normal * cos_theta)
+        .normalize();
+
+    let lightv = (-eyev).reflect(halfway);
+    let n_dot_l = Vector::dot(normal, lightv);
+    let v_dot_h = Vector::dot(eyev, halfway);
+    if n_dot_l <= 0.0 || v_dot_h <= 0.0 {
+        return None;
+    }
+
+    // The chance of this direction is D * n.h / (4 * v.h), and the BRDF is
+    // scaled up by pi
+    let (_, specular) = microfacet(material, base_color, lightv, eyev, normal);
+    let pdf = ggx_distribution(cos_theta, alpha) * cos_theta / (4.0 * v_dot_h);
+    Some((lightv, specular * (n_dot_l / (PI * pdf * specular_chance))))
+}
+
 #[cfg(test)]
 mod test {
     use crate::{
@@ -85,11 +294,18 @@ mod test {
         lights::PointLight,
         patterns::Stripes,
         shapes::Sphere,
-        Point, Vector,
+        Point, Vector, EQUALITY_EPSILON,
     };
 
     use super::*;
 
+    fn visibility(in_shadow: bool) -> Visibility {
+        Visibility {
+            in_shadow,
+            ambient: 1.0,
+        }
+    }
+
     #[test]
     fn default_material() {
         let m = Material::new();
@@ -111,10 +327,13 @@ mod test {
             &m,
             &Shape::new(Sphere),
             &light,
-            position,
+            SurfacePoint {
+                point: position,
+                time: 0.0,
+            },
             eyev,
             normalv,
-            false,
+            visibility(false),
         );
         assert_eq!(result, Color::new(1.9, 1.9, 1.9));
     }
@@ -130,10 +349,13 @@ mod test {
             &m,
             &Shape::new(Sphere),
             &light,
-            position,
+            SurfacePoint {
+                point: position,
+                time: 0.0,
+            },
             eyev,
             normalv,
-            false,
+            visibility(false),
         );
         assert_eq!(result, Color::new(1.0, 1.0, 1.0));
     }
@@ -149,10 +371,13 @@ mod test {
             &m,
             &Shape::new(Sphere),
             &light,
-            position,
+            SurfacePoint {
+                point: position,
+                time: 0.0,
+            },
             eyev,
             normalv,
-            false,
+            visibility(false),
         );
         assert_eq!(result, Color::new(0.7364, 0.7364, 0.7364));
     }
@@ -168,10 +393,13 @@ mod test {
             &m,
             &Shape::new(Sphere),
             &light,
-            position,
+            SurfacePoint {
+                point: position,
+                time: 0.0,
+            },
             eyev,
             normalv,
-            false,
+            visibility(false),
         );
         assert_eq!(result, Color::new(1.6364, 1.6364, 1.6364));
     }
@@ -187,10 +415,13 @@ mod test {
             &m,
             &Shape::new(Sphere),
             &light,
-            position,
+            SurfacePoint {
+                point: position,
+                time: 0.0,
+            },
             eyev,
             normalv,
-            false,
+            visibility(false),
         );
         assert_eq!(result, Color::new(0.1, 0.1, 0.1));
     }
@@ -207,14 +438,46 @@ mod test {
             &m,
             &Shape::new(Sphere),
             &light,
-            position,
+            SurfacePoint {
+                point: position,
+                time: 0.0,
+            },
             eyev,
             normalv,
-            in_shadow,
+            visibility(in_shadow),
         );
         assert_eq!(result, Color::new(0.1, 0.1, 0.1));
     }
 
+    #[test]
+    fn occlusion_scales_only_ambient() {
+        let m = Material::default();
+        let position = Point::new(0.0, 0.0, 0.0);
+        let eyev = Vector::new(0.0, 0.0, -1.0);
+        let normalv = Vector::new(0.0, 0.0, -1.0);
+        let light = PointLight::new(Point::new(0.0, 0.0, -10.0), Color::new(1.0, 1.0, 1.0));
+        let lit = |in_shadow| {
+            let visibility = Visibility {
+                in_shadow,
+                ambient: 0.5,
+            };
+            lighting(
+                &m,
+                &Shape::new(Sphere),
+                &light,
+                SurfacePoint {
+                    point: position,
+                    time: 0.0,
+                },
+                eyev,
+                normalv,
+                visibility,
+            )
+        };
+        assert_eq!(lit(true), Color::new(0.05, 0.05, 0.05));
+        assert_eq!(lit(false), Color::new(1.85, 1.85, 1.85));
+    }
+
     #[test]
     fn lighting_with_pattern() {
         let m = Material {
@@ -231,24 +494,184 @@ mod test {
             &m,
             &Shape::new(Sphere),
             &light,
-            Point::new(0.9, 0.0, 0.0),
+            SurfacePoint {
+                point: Point::new(0.9, 0.0, 0.0),
+                time: 0.0,
+            },
             eyev,
             normal,
-            false,
+            visibility(false),
         );
         let c2 = lighting(
             &m,
             &Shape::new(Sphere),
             &light,
-            Point::new(1.1, 0.0, 0.0),
+            SurfacePoint {
+                point: Point::new(1.1, 0.0, 0.0),
+                time: 0.0,
+            },
             eyev,
             normal,
-            false,
+            visibility(false),
         );
         assert_eq!(c1, WHITE);
         assert_eq!(c2, BLACK);
     }
 
+    #[test]
+    fn dispersion_raises_index_for_shorter_wavelengths() {
+        let m = Material {
+            refractive_index: 1.5,
+            dispersion: 0.01,
+            ..Material::default()
+        };
+        assert_eq!(m.refractive_index_at(None), 1.5);
+        assert_eq!(m.refractive_index_at(Some(REFERENCE_WAVELENGTH)), 1.5);
+        let red = m.refractive_index_at(Some(0.65));
+        let blue = m.refractive_index_at(Some(0.45));
+        assert!(red < 1.5 && 1.5 < blue);
+        assert!((blue - (1.5 + 0.01 * (1.0 / 0.2025 - 1.0 / 0.34727449))).abs() < EQUALITY_EPSILON);
+    }
+
+    #[test]
+    fn transmittance_falls_with_distance() {
+        let m = Material {
+            absorption: Color::new(0.5, 0.0, 2.0),
+            ..Material::default()
+        };
+        assert_eq!(m.transmittance(0.0), WHITE);
+        assert_eq!(
+            m.transmittance(1.0),
+            Color::new((-0.5_f64).exp(), 1.0, (-2.0_f64).exp())
+        );
+    }
+
+    #[test]
+    fn default_material_uses_phong() {
+        let m = Material::default();
+        assert_eq!(m.shading, Shading::Phong);
+        assert_eq!(m.roughness, 0.0);
+        assert_eq!(m.glossy_samples, 1);
+        assert_eq!(m.metallic, 0.0);
+    }
+
+    fn microfacet_lighting(m: &Material, eyev: Vector, light_position: Point) -> Color {
+        let light = PointLight::new(light_position, WHITE);
+        lighting(
+            m,
+            &Shape::new(Sphere),
+            &light,
+            SurfacePoint {
+                point: Point::new(0.0, 0.0, 0.0),
+                time: 0.0,
+            },
+            eyev,
+            Vector::new(0.0, 0.0, -1.0),
+            visibility(false),
+        )
+    }
+
+    #[test]
+    fn microfacet_dielectric_head_on() {
+        let m = Material {
+            shading: Shading::Microfacet,
+            roughness: 0.5,
+            ..Material::default()
+        };
+        let result =
+            microfacet_lighting(&m, Vector::new(0.0, 0.0, -1.0), Point::new(0.0, 0.0, -10.0));
+        assert_eq!(result, Color::new(1.22, 1.22, 1.22));
+    }
+
+    #[test]
+    fn microfacet_metal_head_on() {
+        let gold = Color::new(1.0, 0.8, 0.3);
+        let m = Material {
+            shading: Shading::Microfacet,
+            color: gold,
+            roughness: 0.5,
+            metallic: 1.0,
+            ..Material::default()
+        };
+        let result =
+            microfacet_lighting(&m, Vector::new(0.0, 0.0, -1.0), Point::new(0.0, 0.0, -10.0));
+        assert_eq!(result, Color::new(4.1, 3.28, 1.23));
+    }
+
+    #[test]
+    fn microfacet_light_behind_surface() {
+        let m = Material {
+            shading: Shading::Microfacet,
+            roughness: 0.5,
+            ..Material::default()
+        };
+        let result =
+            microfacet_lighting(&m, Vector::new(0.0, 0.0, -1.0), Point::new(0.0, 0.0, 10.0));
+        assert_eq!(result, Color::new(0.1, 0.1, 0.1));
+    }
+
+    #[test]
+    fn sampling_smooth_metal_mirrors() {
+        let m = Material {
+            shading: Shading::Microfacet,
+            metallic: 1.0,
+            color: Color::new(0.9, 0.6, 0.3),
+            ..Material::default()
+        };
+        let eyev = Vector::new(0.0, 1.0, -1.0).normalize();
+        let normal = Vector::new(0.0, 1.0, 0.0);
+        crate::random::seed(3);
+        for _ in 0..10 {
+            let (direction, weight) = sample_microfacet(&m, m.color, eyev, normal).unwrap();
+            let mirror = Vector::new(0.0, 1.0, 1.0).normalize();
+            assert!(Vector::dot(direction, mirror) > 0.999);
+            assert!((weight.red() - 0.9).abs() < 0.01);
+            assert!(weight.blue() > 0.3 && weight.blue() < 0.35);
+        }
+    }
+
+    #[test]
+    fn sampling_rough_dielectric_reflects_about_its_albedo() {
+        let m = Material {
+            shading: Shading::Microfacet,
+            roughness: 0.5,
+            color: Color::new(0.5, 0.5, 0.5),
+            ..Material::default()
+        };
+        let normal = Vector::new(0.0, 1.0, 0.0);
+        crate::random::seed(3);
+        let samples = 20000;
+        let mut total = 0.0;
+        for _ in 0..samples {
+            if let Some((_, weight)) = sample_microfacet(&m, m.color, normal, normal) {
+                total += weight.red();
+            }
+        }
+        // Half the base colour, plus a little for the specular lobe
+        let albedo = total / samples as f64;
+        assert!(albedo > 0.5 && albedo < 0.6, "{albedo}");
+    }
+
+    #[test]
+    fn microfacet_roughness_spreads_highlight() {
+        let smooth = Material {
+            shading: Shading::Microfacet,
+            roughness: 0.05,
+            metallic: 1.0,
+            ..Material::default()
+        };
+        let rough = Material {
+            roughness: 0.8,
+            ..smooth.clone()
+        };
+        let eyev = Vector::new(0.0, 2_f64.sqrt() / 2.0, -(2_f64.sqrt()) / 2.0);
+        let light_position = Point::new(0.0, 0.0, -10.0);
+        let smooth_result = microfacet_lighting(&smooth, eyev, light_position);
+        let rough_result = microfacet_lighting(&rough, eyev, light_position);
+        assert!(smooth_result.red() < 0.11);
+        assert!(rough_result.red() > 0.2);
+    }
+
     #[test]
     fn reflectivity_for_default_material() {
         let m = Material::default();
@@ -1,4 +1,4 @@
-use crate::{Point, Vector, EQUALITY_EPSILON};
+use crate::{quaternions::Quaternion, Point, Vector, EQUALITY_EPSILON};
 
 pub const IDENTITY: Transform = Transform {
     matrix: Matrix {
@@ -52,7 +52,7 @@ impl Matrix<2, 2> {
 
     pub fn cofactor(&self, i: usize, j: usize) -> Result<f64, MatrixIndexError> {
         let minor = self.minor(i, j)?;
-        if (i + j) % 2 == 0 {
+        if (i + j).is_multiple_of(2) {
             Ok(minor)
         } else {
             Ok(-minor)
@@ -121,7 +121,7 @@ impl Matrix<3, 3> {
 
     pub fn cofactor(&self, i: usize, j: usize) -> Result<f64, MatrixIndexError> {
         let minor = self.minor(i, j)?;
-        if (i + j) % 2 == 0 {
+        if (i + j).is_multiple_of(2) {
             Ok(minor)
         } else {
             Ok(-minor)
@@ -190,7 +190,7 @@ impl Matrix<4, 4> {
 
     pub fn cofactor(&self, i: usize, j: usize) -> Result<f64, MatrixIndexError> {
         let minor = self.minor(i, j)?;
-        if (i + j) % 2 == 0 {
+        if (i + j).is_multiple_of(2) {
             Ok(minor)
         } else {
             Ok(-minor)
@@ -354,6 +354,133 @@ impl Transform {
             matrix: self.matrix.inverse()?,
         })
     }
+
+    /// Blends each entry from `self` at `t = 0` to `other` at `t = 1`.
+    pub fn lerp(&self, other: &Transform, t: f64) -> Transform {
+        let mut entries = self.matrix.entries;
+        for (row, other_row) in entries.iter_mut().zip(other.matrix.entries) {
+            for (entry, other_entry) in row.iter_mut().zip(other_row) {
+                *entry += (other_entry - *entry) * t;
+            }
+        }
+        Transform {
+            matrix: Matrix::new(entries),
+        }
+    }
+
+    /// Splits the transform into a scale, then a rotation, then a
+    /// translation. Returns `None` if it flattens space or shears it, since
+    /// neither can be rebuilt from those parts.
+    pub fn decompose(&self) -> Option<Decomposition> {
+        let e = &self.matrix.entries;
+        let translation = Vector::new(e[0][3], e[1][3], e[2][3]);
+        let columns = [0, 1, 2].map(|j| Vector::new(e[0][j], e[1][j], e[2][j]));
+        let mut scale = columns.map(|column| column.magnitude());
+        if scale.iter().any(|&s| s < EQUALITY_EPSILON) {
+            return None;
+        }
+        // A mirror image can't be a rotation, so put the flip in the scale
+        if self.submatrix(3, 3).ok()?.determinant() < 0.0 {
+            scale[0] = -scale[0];
+        }
+
+        let axes = [0, 1, 2].map(|j| columns[j] / scale[j]);
+        let rotation = Quaternion::from_rotation(&Matrix::new([
+            [axes[0].x, axes[1].x, axes[2].x],
+            [axes[0].y, axes[1].y, axes[2].y],
+            [axes[0].z, axes[1].z, axes[2].z],
+        ]));
+        let decomposition = Decomposition {
+            translation,
+            rotation,
+            scale: Vector::new(scale[0], scale[1], scale[2]),
+        };
+
+        (decomposition.transform() == *self).then_some(decomposition)
+    }
+
+    /// Blends from `self` at `t = 0` to `other` at `t = 1`, moving and
+    /// scaling in a straight line while turning at a constant rate. Falls
+    /// back to [`Transform::lerp`] if either can't be decomposed.
+    pub fn slerp(&self, other: &Transform, t: f64) -> Transform {
+        match (self.decompose(), other.decompose()) {
+            (Some(a), Some(b)) => a.interpolate(&b, t).transform(),
+            _ => self.lerp(other, t),
+        }
+    }
+}
+
+/// A transform as a scale, then a rotation, then a translation.
+#[derive(Debug, Default, Copy, Clone, PartialEq)]
+pub struct Decomposition {
+    pub translation: Vector,
+    pub rotation: Quaternion,
+    pub scale: Vector,
+}
+
+impl Decomposition {
+    pub fn transform(&self) -> Transform {
+        let Vector { x, y, z } = self.translation;
+        let Vector {
+            x: sx,
+            y: sy,
+            z: sz,
+        } = self.scale;
+        let scaled = &self.rotation.to_transform()
+            * &Transform::new([
+                [sx, 0.0, 0.0, 0.0],
+                [0.0, sy, 0.0, 0.0],
+                [0.0, 0.0, sz, 0.0],
+                [0.0, 0.0, 0.0, 1.0],
+            ])
+            .expect("casting transform");
+
+        let mut entries = scaled.matrix.entries;
+        entries[0][3] = x;
+        entries[1][3] = y;
+        entries[2][3] = z;
+        Transform {
+            matrix: Matrix::new(entries),
+        }
+    }
+
+    /// The transform that undoes this one, built from its parts rather than
+    /// by inverting a matrix. Returns `None` if it flattens space.
+    pub fn inverse(&self) -> Option<Transform> {
+        let Vector {
+            x: sx,
+            y: sy,
+            z: sz,
+        } = self.scale;
+        if [sx, sy, sz].iter().any(|s| s.abs() < EQUALITY_EPSILON) {
+            return None;
+        }
+        let unscaled = &Transform::new([
+            [1.0 / sx, 0.0, 0.0, 0.0],
+            [0.0, 1.0 / sy, 0.0, 0.0],
+            [0.0, 0.0, 1.0 / sz, 0.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ])
+        .expect("casting transform")
+            * &self.rotation.conjugate().to_transform();
+
+        let Vector { x, y, z } = -(&unscaled * self.translation);
+        let mut entries = unscaled.matrix.entries;
+        entries[0][3] = x;
+        entries[1][3] = y;
+        entries[2][3] = z;
+        Some(Transform {
+            matrix: Matrix::new(entries),
+        })
+    }
+
+    pub fn interpolate(&self, other: &Decomposition, t: f64) -> Decomposition {
+        Decomposition {
+            translation: self.translation + (other.translation - self.translation) * t,
+            rotation: self.rotation.slerp(other.rotation, t),
+            scale: self.scale + (other.scale - self.scale) * t,
+        }
+    }
 }
 
 impl std::ops::Mul for &Transform {
@@ -391,7 +518,10 @@ impl std::ops::Mul<Vector> for &Transform {
 
 #[cfg(test)]
 mod test {
+    use std::f64::consts::PI;
+
     use super::*;
+    use crate::transformations::{rotation_z, scaling, shearing, translation, Builder};
 
     #[test]
     fn construct_matrix() {
@@ -749,4 +879,93 @@ mod test {
         ]);
         assert_eq!(a.inverse(), None);
     }
+
+    #[test]
+    fn lerp_transforms() {
+        let a = Transform::new([
+            [1.0, 0.0, 0.0, 0.0],
+            [0.0, 1.0, 0.0, 2.0],
+            [0.0, 0.0, 1.0, 0.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ])
+        .unwrap();
+        let b = Transform::new([
+            [3.0, 0.0, 0.0, 4.0],
+            [0.0, 1.0, 0.0, 0.0],
+            [0.0, 0.0, 1.0, 0.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ])
+        .unwrap();
+        let halfway = Transform::new([
+            [2.0, 0.0, 0.0, 2.0],
+            [0.0, 1.0, 0.0, 1.0],
+            [0.0, 0.0, 1.0, 0.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ])
+        .unwrap();
+        assert_eq!(a.lerp(&b, 0.0), a);
+        assert_eq!(a.lerp(&b, 1.0), b);
+        assert_eq!(a.lerp(&b, 0.5), halfway);
+    }
+
+    #[test]
+    fn decompose_transform() {
+        let transform = Builder::new()
+            .scaling(2.0, -1.0, 3.0)
+            .rotation_y(PI / 3.0)
+            .translation(1.0, 2.0, 3.0)
+            .transform();
+        let decomposition = transform.decompose().unwrap();
+        assert_eq!(decomposition.translation, Vector::new(1.0, 2.0, 3.0));
+        assert_eq!(decomposition.scale.y.abs(), 1.0);
+        assert_eq!(decomposition.transform(), transform);
+    }
+
+    #[test]
+    fn decomposition_inverse_matches_matrix_inverse() {
+        let transform = Builder::new()
+            .scaling(2.0, -1.0, 3.0)
+            .rotation_y(PI / 3.0)
+            .translation(1.0, 2.0, 3.0)
+            .transform();
+        let decomposition = transform.decompose().unwrap();
+        assert_eq!(decomposition.inverse(), transform.inverse());
+
+        let flat = Decomposition {
+            scale: Vector::new(1.0, 0.0, 1.0),
+            ..decomposition
+        };
+        assert_eq!(flat.inverse(), None);
+    }
+
+    #[test]
+    fn decompose_rejects_shear_and_flattening() {
+        assert_eq!(shearing(1.0, 0.0, 0.0, 0.0, 0.0, 0.0).decompose(), None);
+        assert_eq!(scaling(1.0, 0.0, 1.0).decompose(), None);
+    }
+
+    #[test]
+    fn slerp_transforms_turns_evenly() {
+        let a = translation(0.0, 0.0, 0.0);
+        let b = Builder::new()
+            .scaling(3.0, 3.0, 3.0)
+            .rotation_z(PI / 2.0)
+            .translation(4.0, 0.0, 0.0)
+            .transform();
+        let halfway = Builder::new()
+            .scaling(2.0, 2.0, 2.0)
+            .rotation_z(PI / 4.0)
+            .translation(2.0, 0.0, 0.0)
+            .transform();
+        assert_eq!(a.slerp(&b, 0.0), a);
+        assert_eq!(a.slerp(&b, 1.0), b);
+        assert_eq!(a.slerp(&b, 0.5), halfway);
+    }
+
+    #[test]
+    fn slerp_falls_back_to_lerp_for_shear() {
+        let a = shearing(1.0, 0.0, 0.0, 0.0, 0.0, 0.0);
+        let b = rotation_z(PI / 2.0);
+        assert_eq!(a.slerp(&b, 0.5), a.lerp(&b, 0.5));
+    }
 }
@@ -0,0 +1,115 @@
+use crate::{canvas::Color, rays::Ray, shapes::Shape};
+
+const DEFAULT_STEPS: usize = 16;
+
+// Beyond this much attenuation, a medium is treated as opaque
+const HORIZON_TRANSMITTANCE: f64 = 0.001;
+
+/// A uniform cloud of particles, like fog or smoke, that dims light passing
+/// through it and scatters light from the world's light towards the eye.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Medium {
+    /// The fraction of light lost per unit of distance.
+    pub density: f64,
+    /// The colour of light scattered by the particles.
+    pub color: Color,
+    /// How many points along a ray are checked for light to scatter.
+    pub steps: usize,
+}
+
+impl Medium {
+    pub fn new(density: f64, color: Color) -> Self {
+        Medium {
+            density,
+            color,
+            steps: DEFAULT_STEPS,
+        }
+    }
+
+    /// The fraction of light left after travelling `distance` through the
+    /// medium.
+    pub fn transmittance(&self, distance: f64) -> f64 {
+        (-self.density * distance).exp()
+    }
+
+    /// How far light can get through the medium before it's all but gone.
+    pub fn horizon(&self) -> f64 {
+        -HORIZON_TRANSMITTANCE.ln() / self.density
+    }
+}
+
+/// A medium filling the inside of a closed shape, which itself isn't drawn.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Volume {
+    pub bounds: Shape,
+    pub medium: Medium,
+}
+
+impl Volume {
+    pub fn new(bounds: Shape, medium: Medium) -> Self {
+        Volume { bounds, medium }
+    }
+
+    /// The ranges of `t` along `ray` that are inside the volume. An odd
+    /// number of crossings means the ray starts inside, so it enters at its
+    /// origin.
+    pub fn segments(&self, ray: &Ray) -> Vec<(f64, f64)> {
+        let mut crossings = self
+            .bounds
+            .intersect(ray)
+            .iter()
+            .map(|intersection| intersection.t)
+            .collect::<Vec<_>>();
+        if crossings.len() % 2 == 1 {
+            crossings.insert(0, 0.0);
+        }
+        crossings
+            .chunks_exact(2)
+            .map(|pair| (pair[0], pair[1]))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        canvas::WHITE,
+        shapes::{Plane, Sphere},
+        transformations::scaling,
+        Point, Vector, EQUALITY_EPSILON,
+    };
+
+    use super::*;
+
+    #[test]
+    fn transmittance_falls_with_distance() {
+        let medium = Medium::new(0.5, WHITE);
+        assert_eq!(medium.transmittance(0.0), 1.0);
+        assert!((medium.transmittance(2.0) - (-1.0_f64).exp()).abs() < EQUALITY_EPSILON);
+    }
+
+    #[test]
+    fn horizon_is_where_light_runs_out() {
+        let medium = Medium::new(0.25, WHITE);
+        let horizon = medium.horizon();
+        assert!((medium.transmittance(horizon) - HORIZON_TRANSMITTANCE).abs() < EQUALITY_EPSILON);
+    }
+
+    #[test]
+    fn volume_segments_span_the_inside() {
+        let mut bounds = Shape::new(Sphere);
+        bounds.set_transform(scaling(2.0, 2.0, 2.0)).unwrap();
+        let volume = Volume::new(bounds, Medium::new(1.0, WHITE));
+        let r = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+        assert_eq!(volume.segments(&r), vec![(3.0, 7.0)]);
+        let r = Ray::new(Point::new(0.0, 5.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+        assert!(volume.segments(&r).is_empty());
+    }
+
+    #[test]
+    fn ray_crossing_the_bounds_once_starts_inside() {
+        let volume = Volume::new(Shape::new(Plane), Medium::new(1.0, WHITE));
+        let r = Ray::new(Point::new(0.0, -1.0, 0.0), Vector::new(0.0, 1.0, 0.0));
+        assert_eq!(volume.segments(&r), vec![(0.0, 1.0)]);
+    }
+}
@@ -0,0 +1,191 @@
+use crate::{random::Rng, Point, Vector};
+
+/// Seeded gradient noise, after Ken Perlin's "improved noise".
+///
+/// Two generators built from the same seed always produce the same values.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Perlin {
+    permutation: Vec<usize>,
+}
+
+impl Perlin {
+    pub fn new(seed: u64) -> Self {
+        let mut rng = Rng::new(seed);
+        let mut table = (0..256).collect::<Vec<usize>>();
+        for i in (1..table.len()).rev() {
+            let j = rng.below(i + 1);
+            table.swap(i, j);
+        }
+
+        // Doubled so lookups like `p[p[x] + y]` never need wrapping
+        let permutation = table.iter().chain(table.iter()).copied().collect();
+        Perlin { permutation }
+    }
+
+    /// Returns a smoothly varying value in roughly `[-1, 1]`, which is zero at
+    /// every integer lattice point.
+    pub fn noise(&self, point: Point) -> f64 {
+        let p = &self.permutation;
+
+        let xi = point.x.floor().rem_euclid(256.0) as usize;
+        let yi = point.y.floor().rem_euclid(256.0) as usize;
+        let zi = point.z.floor().rem_euclid(256.0) as usize;
+
+        let x = point.x - point.x.floor();
+        let y = point.y - point.y.floor();
+        let z = point.z - point.z.floor();
+
+        let u = fade(x);
+        let v = fade(y);
+        let w = fade(z);
+
+        let a = p[xi] + yi;
+        let aa = p[a] + zi;
+        let ab = p[a + 1] + zi;
+        let b = p[xi + 1] + yi;
+        let ba = p[b] + zi;
+        let bb = p[b + 1] + zi;
+
+        lerp(
+            w,
+            lerp(
+                v,
+                lerp(u, grad(p[aa], x, y, z), grad(p[ba], x - 1.0, y, z)),
+                lerp(
+                    u,
+                    grad(p[ab], x, y - 1.0, z),
+                    grad(p[bb], x - 1.0, y - 1.0, z),
+                ),
+            ),
+            lerp(
+                v,
+                lerp(
+                    u,
+                    grad(p[aa + 1], x, y, z - 1.0),
+                    grad(p[ba + 1], x - 1.0, y, z - 1.0),
+                ),
+                lerp(
+                    u,
+                    grad(p[ab + 1], x, y - 1.0, z - 1.0),
+                    grad(p[bb + 1], x - 1.0, y - 1.0, z - 1.0),
+                ),
+            ),
+        )
+    }
+
+    /// Three decorrelated noise values, for displacing points and normals.
+    pub fn vector_noise(&self, point: Point) -> Vector {
+        Vector::new(
+            self.noise(point),
+            self.noise(point + Vector::new(31.416, 47.853, 12.679)),
+            self.noise(point + Vector::new(-19.321, 7.137, 83.291)),
+        )
+    }
+
+    /// Sums `octaves` layers of absolute noise, each at double the frequency and
+    /// half the amplitude of the last.
+    pub fn turbulence(&self, point: Point, octaves: usize) -> f64 {
+        let mut total = 0.0;
+        let mut frequency = 1.0;
+        let mut amplitude = 1.0;
+        for _ in 0..octaves {
+            let scaled = Point::new(
+                point.x * frequency,
+                point.y * frequency,
+                point.z * frequency,
+            );
+            total += self.noise(scaled).abs() * amplitude;
+            frequency *= 2.0;
+            amplitude *= 0.5;
+        }
+        total
+    }
+}
+
+fn fade(t: f64) -> f64 {
+    t * t * t * (t * (t * 6.0 - 15.0) + 10.0)
+}
+
+fn lerp(t: f64, a: f64, b: f64) -> f64 {
+    a + t * (b - a)
+}
+
+fn grad(hash: usize, x: f64, y: f64, z: f64) -> f64 {
+    let h = hash & 15;
+    let u = if h < 8 { x } else { y };
+    let v = if h < 4 {
+        y
+    } else if h == 12 || h == 14 {
+        x
+    } else {
+        z
+    };
+    (if h & 1 == 0 { u } else { -u }) + (if h & 2 == 0 { v } else { -v })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn noise_is_deterministic_for_seed() {
+        let a = Perlin::new(1234);
+        let b = Perlin::new(1234);
+        let point = Point::new(1.3, -2.7, 0.45);
+        assert_eq!(a, b);
+        assert_eq!(a.noise(point), b.noise(point));
+    }
+
+    #[test]
+    fn different_seeds_give_different_noise() {
+        let a = Perlin::new(1);
+        let b = Perlin::new(2);
+        let point = Point::new(1.3, -2.7, 0.45);
+        assert_ne!(a.noise(point), b.noise(point));
+    }
+
+    #[test]
+    fn noise_is_zero_on_lattice() {
+        let perlin = Perlin::new(0);
+        assert_eq!(perlin.noise(Point::new(0.0, 0.0, 0.0)), 0.0);
+        assert_eq!(perlin.noise(Point::new(3.0, -5.0, 12.0)), 0.0);
+    }
+
+    #[test]
+    fn noise_is_bounded_and_varies() {
+        let perlin = Perlin::new(99);
+        let mut min = f64::MAX;
+        let mut max = f64::MIN;
+        for i in 0..1000 {
+            let t = i as f64 * 0.137;
+            let n = perlin.noise(Point::new(t, t * 0.5, -t * 0.25));
+            min = min.min(n);
+            max = max.max(n);
+        }
+        assert!(min >= -1.0 && max <= 1.0);
+        assert!(max - min > 0.5);
+    }
+
+    #[test]
+    fn noise_is_continuous() {
+        let perlin = Perlin::new(5);
+        let point = Point::new(0.5, 0.25, 0.75);
+        let nearby = point + Vector::new(0.0001, 0.0, 0.0);
+        assert!((perlin.noise(point) - perlin.noise(nearby)).abs() < 0.001);
+    }
+
+    #[test]
+    fn turbulence_is_non_negative() {
+        let perlin = Perlin::new(5);
+        for i in 0..100 {
+            let t = i as f64 * 0.31;
+            assert!(perlin.turbulence(Point::new(t, -t, t * 2.0), 4) >= 0.0);
+        }
+    }
+
+    #[test]
+    fn turbulence_with_no_octaves_is_zero() {
+        let perlin = Perlin::new(5);
+        assert_eq!(perlin.turbulence(Point::new(0.3, 0.2, 0.1), 0), 0.0);
+    }
+}
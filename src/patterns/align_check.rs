@@ -0,0 +1,74 @@
+use crate::canvas::Color;
+
+use super::UvPatternModel;
+
+/// A 2D pattern with a distinct colour in each corner, useful for checking
+/// that a UV mapping is oriented the way you expect.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AlignCheck {
+    pub main: Color,
+    pub upper_left: Color,
+    pub upper_right: Color,
+    pub bottom_left: Color,
+    pub bottom_right: Color,
+}
+
+impl AlignCheck {
+    pub fn new(
+        main: Color,
+        upper_left: Color,
+        upper_right: Color,
+        bottom_left: Color,
+        bottom_right: Color,
+    ) -> Self {
+        AlignCheck {
+            main,
+            upper_left,
+            upper_right,
+            bottom_left,
+            bottom_right,
+        }
+    }
+}
+
+impl UvPatternModel for AlignCheck {
+    fn uv_at(&self, u: f64, v: f64) -> Color {
+        if v > 0.8 {
+            if u < 0.2 {
+                return self.upper_left;
+            }
+            if u > 0.8 {
+                return self.upper_right;
+            }
+        } else if v < 0.2 {
+            if u < 0.2 {
+                return self.bottom_left;
+            }
+            if u > 0.8 {
+                return self.bottom_right;
+            }
+        }
+
+        self.main
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn layout_of_align_check() {
+        let main = Color::new(1.0, 1.0, 1.0);
+        let ul = Color::new(1.0, 0.0, 0.0);
+        let ur = Color::new(1.0, 1.0, 0.0);
+        let bl = Color::new(0.0, 1.0, 0.0);
+        let br = Color::new(0.0, 1.0, 1.0);
+        let pattern = AlignCheck::new(main, ul, ur, bl, br);
+        assert_eq!(pattern.uv_at(0.5, 0.5), main);
+        assert_eq!(pattern.uv_at(0.1, 0.9), ul);
+        assert_eq!(pattern.uv_at(0.9, 0.9), ur);
+        assert_eq!(pattern.uv_at(0.1, 0.1), bl);
+        assert_eq!(pattern.uv_at(0.9, 0.1), br);
+    }
+}
@@ -0,0 +1,72 @@
+use crate::{canvas::Color, Point};
+
+use super::{Pattern, PatternModel};
+
+/// Mixes two patterns together, with `weight` being how much of `b` to use.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Blend {
+    pub a: Pattern,
+    pub b: Pattern,
+    pub weight: f64,
+}
+
+impl Blend {
+    /// Averages the two patterns.
+    pub fn new(a: impl Into<Pattern>, b: impl Into<Pattern>) -> Self {
+        Blend::with_weight(a, b, 0.5)
+    }
+
+    pub fn with_weight(a: impl Into<Pattern>, b: impl Into<Pattern>, weight: f64) -> Self {
+        Blend {
+            a: a.into(),
+            b: b.into(),
+            weight,
+        }
+    }
+}
+
+impl PatternModel for Blend {
+    fn at(&self, point: Point) -> Color {
+        self.a.at(point) * (1.0 - self.weight) + self.b.at(point) * self.weight
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::f64::consts::PI;
+
+    use crate::{
+        canvas::{BLACK, WHITE},
+        patterns::Stripes,
+        transformations::rotation_y,
+    };
+
+    use super::*;
+
+    #[test]
+    fn blend_averages_patterns() {
+        let horizontal = Pattern::new(Stripes::new(WHITE, BLACK));
+        let mut vertical = Pattern::new(Stripes::new(WHITE, BLACK));
+        vertical.set_transform(rotation_y(PI / 2.0)).unwrap();
+        let blend = Blend::new(horizontal, vertical);
+        assert_eq!(blend.at(Point::new(0.5, 0.0, -0.5)), WHITE);
+        assert_eq!(
+            blend.at(Point::new(1.5, 0.0, -0.5)),
+            Color::new(0.5, 0.5, 0.5)
+        );
+        assert_eq!(
+            blend.at(Point::new(0.5, 0.0, -1.5)),
+            Color::new(0.5, 0.5, 0.5)
+        );
+        assert_eq!(blend.at(Point::new(1.5, 0.0, -1.5)), BLACK);
+    }
+
+    #[test]
+    fn blend_with_weight() {
+        let blend = Blend::with_weight(WHITE, BLACK, 0.25);
+        assert_eq!(
+            blend.at(Point::new(0.0, 0.0, 0.0)),
+            Color::new(0.75, 0.75, 0.75)
+        );
+    }
+}
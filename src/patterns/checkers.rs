@@ -1,16 +1,19 @@
 use crate::{canvas::Color, Point};
 
-use super::PatternModel;
+use super::{Pattern, PatternModel};
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct Checkers {
-    pub a: Color,
-    pub b: Color,
+    pub a: Pattern,
+    pub b: Pattern,
 }
 
 impl Checkers {
-    pub fn new(a: Color, b: Color) -> Self {
-        Checkers { a, b }
+    pub fn new(a: impl Into<Pattern>, b: impl Into<Pattern>) -> Self {
+        Checkers {
+            a: a.into(),
+            b: b.into(),
+        }
     }
 }
 
@@ -24,16 +27,22 @@ impl PatternModel for Checkers {
             % 2
             == 0
         {
-            self.a
+            self.a.at(point)
         } else {
-            self.b
+            self.b.at(point)
         }
     }
 }
 
 #[cfg(test)]
 mod test {
-    use crate::canvas::{BLACK, WHITE};
+    use std::f64::consts::PI;
+
+    use crate::{
+        canvas::{BLACK, WHITE},
+        patterns::Stripes,
+        transformations::{scaling, Builder},
+    };
 
     use super::*;
 
@@ -60,4 +69,27 @@ mod test {
         assert_eq!(checkers.at(Point::new(0.0, 0.0, 0.99)), WHITE);
         assert_eq!(checkers.at(Point::new(0.0, 0.0, 1.01)), BLACK);
     }
+
+    #[test]
+    fn checkers_of_stripes() {
+        let mut horizontal = Pattern::new(Stripes::new(WHITE, BLACK));
+        horizontal.set_transform(scaling(0.5, 1.0, 1.0)).unwrap();
+        let mut vertical = Pattern::new(Stripes::new(WHITE, BLACK));
+        vertical
+            .set_transform(
+                Builder::new()
+                    .scaling(0.5, 1.0, 1.0)
+                    .rotation_y(PI / 2.0)
+                    .transform(),
+            )
+            .unwrap();
+        let model = Checkers::new(horizontal, vertical);
+
+        // First square: stripes vary in x
+        assert_eq!(model.at(Point::new(0.25, 0.0, 0.5)), WHITE);
+        assert_eq!(model.at(Point::new(0.75, 0.0, 0.5)), BLACK);
+        // Next square over in z: stripes vary in z
+        assert_eq!(model.at(Point::new(0.5, 0.0, 1.25)), BLACK);
+        assert_eq!(model.at(Point::new(0.5, 0.0, 1.75)), WHITE);
+    }
 }
@@ -1,24 +1,126 @@
 use crate::{canvas::Color, Point};
 
-use super::PatternModel;
+use super::{Pattern, PatternModel};
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct Gradient {
-    a: Color,
-    b: Color,
+    a: Pattern,
+    b: Pattern,
 }
 
 impl Gradient {
-    pub fn new(a: Color, b: Color) -> Self {
-        Gradient { a, b }
+    pub fn new(a: impl Into<Pattern>, b: impl Into<Pattern>) -> Self {
+        Gradient {
+            a: a.into(),
+            b: b.into(),
+        }
     }
 }
 
 impl PatternModel for Gradient {
     fn at(&self, point: Point) -> Color {
-        let distance = self.b - self.a;
+        let a = self.a.at(point);
+        let distance = self.b.at(point) - a;
         let fraction = point.x - point.x.floor();
-        self.a + distance * fraction
+        a + distance * fraction
+    }
+}
+
+/// How a [`ColorRamp`] fills the space between two stops.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub enum Interpolation {
+    #[default]
+    Linear,
+    /// Eases in and out of each stop with a smoothstep curve.
+    Smooth,
+    /// Holds each stop's colour until the next stop.
+    Constant,
+}
+
+/// A list of colours at positions along a line, with colours in between
+/// interpolated. Before the first stop and after the last, the colour is
+/// clamped to that stop's colour.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ColorRamp {
+    stops: Vec<(f64, Color)>,
+    pub interpolation: Interpolation,
+}
+
+impl ColorRamp {
+    pub fn new(mut stops: Vec<(f64, Color)>) -> Self {
+        stops.sort_by(|x, y| x.0.total_cmp(&y.0));
+        ColorRamp {
+            stops,
+            interpolation: Interpolation::default(),
+        }
+    }
+
+    pub fn with_interpolation(self, interpolation: Interpolation) -> Self {
+        ColorRamp {
+            interpolation,
+            ..self
+        }
+    }
+
+    pub fn stops(&self) -> &[(f64, Color)] {
+        &self.stops
+    }
+
+    pub fn at(&self, position: f64) -> Color {
+        let after = self.stops.partition_point(|stop| stop.0 <= position);
+        if after == 0 {
+            return self.stops.first().map_or(Color::default(), |stop| stop.1);
+        }
+        if after == self.stops.len() {
+            return self.stops[after - 1].1;
+        }
+
+        let (start, a) = self.stops[after - 1];
+        let (end, b) = self.stops[after];
+        let fraction = (position - start) / (end - start);
+        let fraction = match self.interpolation {
+            Interpolation::Linear => fraction,
+            Interpolation::Smooth => fraction * fraction * (3.0 - 2.0 * fraction),
+            Interpolation::Constant => 0.0,
+        };
+        a + (b - a) * fraction
+    }
+}
+
+/// A gradient along x through any number of colour stops.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MultiStopGradient {
+    pub ramp: ColorRamp,
+}
+
+impl MultiStopGradient {
+    pub fn new(ramp: ColorRamp) -> Self {
+        MultiStopGradient { ramp }
+    }
+}
+
+impl PatternModel for MultiStopGradient {
+    fn at(&self, point: Point) -> Color {
+        self.ramp.at(point.x)
+    }
+}
+
+/// A gradient by distance from the pattern origin.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RadialGradient {
+    pub ramp: ColorRamp,
+}
+
+impl RadialGradient {
+    pub fn new(ramp: ColorRamp) -> Self {
+        RadialGradient { ramp }
+    }
+}
+
+impl PatternModel for RadialGradient {
+    fn at(&self, point: Point) -> Color {
+        let distance = (point - Point::new(0.0, 0.0, 0.0)).magnitude();
+        self.ramp.at(distance)
     }
 }
 
@@ -45,4 +147,85 @@ mod test {
             Color::new(0.25, 0.25, 0.25)
         );
     }
+
+    fn red_green_blue() -> ColorRamp {
+        ColorRamp::new(vec![
+            (1.0, Color::new(0.0, 0.0, 1.0)),
+            (0.0, Color::new(1.0, 0.0, 0.0)),
+            (0.5, Color::new(0.0, 1.0, 0.0)),
+        ])
+    }
+
+    #[test]
+    fn ramp_sorts_stops() {
+        let ramp = red_green_blue();
+        let positions = ramp.stops().iter().map(|stop| stop.0).collect::<Vec<_>>();
+        assert_eq!(positions, vec![0.0, 0.5, 1.0]);
+    }
+
+    #[test]
+    fn ramp_linear_interpolation() {
+        let ramp = red_green_blue();
+        assert_eq!(ramp.at(0.0), Color::new(1.0, 0.0, 0.0));
+        assert_eq!(ramp.at(0.25), Color::new(0.5, 0.5, 0.0));
+        assert_eq!(ramp.at(0.5), Color::new(0.0, 1.0, 0.0));
+        assert_eq!(ramp.at(0.875), Color::new(0.0, 0.25, 0.75));
+        assert_eq!(ramp.at(1.0), Color::new(0.0, 0.0, 1.0));
+    }
+
+    #[test]
+    fn ramp_clamps_outside_stops() {
+        let ramp = red_green_blue();
+        assert_eq!(ramp.at(-3.0), Color::new(1.0, 0.0, 0.0));
+        assert_eq!(ramp.at(7.0), Color::new(0.0, 0.0, 1.0));
+    }
+
+    #[test]
+    fn ramp_smooth_interpolation() {
+        let ramp = red_green_blue().with_interpolation(Interpolation::Smooth);
+        assert_eq!(ramp.at(0.25), Color::new(0.5, 0.5, 0.0));
+        assert_eq!(ramp.at(0.125), Color::new(0.84375, 0.15625, 0.0));
+    }
+
+    #[test]
+    fn ramp_constant_interpolation() {
+        let ramp = red_green_blue().with_interpolation(Interpolation::Constant);
+        assert_eq!(ramp.at(0.49), Color::new(1.0, 0.0, 0.0));
+        assert_eq!(ramp.at(0.5), Color::new(0.0, 1.0, 0.0));
+        assert_eq!(ramp.at(0.99), Color::new(0.0, 1.0, 0.0));
+    }
+
+    #[test]
+    fn empty_ramp_is_black() {
+        let ramp = ColorRamp::new(vec![]);
+        assert_eq!(ramp.at(0.5), BLACK);
+    }
+
+    #[test]
+    fn multi_stop_gradient_varies_in_x_only() {
+        let gradient = MultiStopGradient::new(red_green_blue());
+        assert_eq!(
+            gradient.at(Point::new(0.25, 0.0, 0.0)),
+            Color::new(0.5, 0.5, 0.0)
+        );
+        assert_eq!(
+            gradient.at(Point::new(0.25, 3.0, -2.0)),
+            Color::new(0.5, 0.5, 0.0)
+        );
+    }
+
+    #[test]
+    fn radial_gradient_by_distance_from_origin() {
+        let gradient = RadialGradient::new(ColorRamp::new(vec![(0.0, WHITE), (2.0, BLACK)]));
+        assert_eq!(gradient.at(Point::new(0.0, 0.0, 0.0)), WHITE);
+        assert_eq!(
+            gradient.at(Point::new(0.0, 1.0, 0.0)),
+            Color::new(0.5, 0.5, 0.5)
+        );
+        assert_eq!(
+            gradient.at(Point::new(0.6, 0.0, -0.8)),
+            Color::new(0.5, 0.5, 0.5)
+        );
+        assert_eq!(gradient.at(Point::new(3.0, 3.0, 3.0)), BLACK);
+    }
 }
@@ -0,0 +1,54 @@
+use std::f64::consts::PI;
+
+use crate::{canvas::Color, noise::Perlin, Point};
+
+use super::PatternModel;
+
+/// Veins running across x, bent by turbulence.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Marble {
+    pub a: Color,
+    pub b: Color,
+    pub noise: Perlin,
+    pub octaves: usize,
+    pub strength: f64,
+}
+
+impl Marble {
+    pub fn new(a: Color, b: Color, seed: u64) -> Self {
+        Marble {
+            a,
+            b,
+            noise: Perlin::new(seed),
+            octaves: 6,
+            strength: 4.0,
+        }
+    }
+}
+
+impl PatternModel for Marble {
+    fn at(&self, point: Point) -> Color {
+        let turbulence = self.noise.turbulence(point, self.octaves);
+        let fraction = 0.5 * (1.0 + ((point.x + self.strength * turbulence) * PI).sin());
+        self.a + (self.b - self.a) * fraction
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::canvas::{BLACK, WHITE};
+
+    use super::*;
+
+    #[test]
+    fn marble_without_turbulence_is_sine_veins() {
+        let mut marble = Marble::new(BLACK, WHITE, 0);
+        marble.strength = 0.0;
+        assert_eq!(
+            marble.at(Point::new(0.0, 0.3, 0.7)),
+            Color::new(0.5, 0.5, 0.5)
+        );
+        assert_eq!(marble.at(Point::new(0.5, 0.3, 0.7)), WHITE);
+        assert_eq!(marble.at(Point::new(-0.5, 0.3, 0.7)), BLACK);
+    }
+}
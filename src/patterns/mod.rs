@@ -1,14 +1,37 @@
 use std::{any::Any, fmt::Debug};
 
+mod align_check;
+mod blend;
 mod checkers;
 mod gradients;
+mod marble;
+mod perturbed;
 mod rings;
+mod solid;
 mod stripes;
-
+mod turbulence;
+mod uv;
+mod uv_checkers;
+mod uv_image;
+mod wood;
+
+pub use align_check::AlignCheck;
+pub use blend::Blend;
 pub use checkers::Checkers;
-pub use gradients::Gradient;
+pub use gradients::{ColorRamp, Gradient, Interpolation, MultiStopGradient, RadialGradient};
+pub use marble::Marble;
+pub use perturbed::Perturbed;
 pub use rings::Rings;
+pub use solid::Solid;
 pub use stripes::Stripes;
+pub use turbulence::Turbulence;
+pub use uv::{
+    cube_map, cylindrical_map, planar_map, spherical_map, CubeFace, CubeMap, TextureMap, UvMapping,
+    UvPatternModel,
+};
+pub use uv_checkers::UvCheckers;
+pub use uv_image::UvImage;
+pub use wood::Wood;
 
 use crate::{
     canvas::Color,
@@ -48,7 +71,7 @@ impl<T: PatternModel> DynamicPatternModel for T {
         other
             .as_any()
             .downcast_ref::<Self>()
-            .map_or(false, |other| self == other)
+            .is_some_and(|other| self == other)
     }
 }
 
@@ -57,6 +80,9 @@ pub struct Pattern {
     transform: Transform,
     inverse: Transform,
     model: Box<dyn DynamicPatternModel>,
+    // Set when the model is a `Solid`, which looks the same wherever it's
+    // evaluated
+    solid: Option<Color>,
 }
 
 #[derive(Debug, Default, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
@@ -64,10 +90,14 @@ pub struct NoInverseTransformError;
 
 impl Pattern {
     pub fn new(model: impl PatternModel) -> Pattern {
+        let solid = (&model as &dyn Any)
+            .downcast_ref::<Solid>()
+            .map(|solid| solid.0);
         Pattern {
             transform: IDENTITY,
             inverse: IDENTITY,
             model: Box::new(model),
+            solid,
         }
     }
 
@@ -77,11 +107,24 @@ impl Pattern {
         Ok(())
     }
 
-    pub fn at_shape(&self, shape: &Shape, point: Point) -> Color {
-        let shape_point = shape.get_inverse_transform() * point;
-        let pattern_point = &self.inverse * shape_point;
+    /// Evaluates the pattern at a point given in the space this pattern's own
+    /// transform is relative to, such as object space or a parent pattern's space.
+    pub fn at(&self, point: Point) -> Color {
+        // Plain colours are the leaves of most nested patterns, so skip the
+        // transform and the dynamic call for them
+        if let Some(color) = self.solid {
+            return color;
+        }
+        let pattern_point = &self.inverse * point;
         self.model.at(pattern_point)
     }
+
+    /// Evaluates the pattern at a world space `point` on `shape`, where the
+    /// shape is at `time`.
+    pub fn at_shape(&self, shape: &Shape, point: Point, time: f64) -> Color {
+        let shape_point = shape.inverse_at(time).as_ref() * point;
+        self.at(shape_point)
+    }
 }
 
 impl Clone for Pattern {
@@ -90,6 +133,7 @@ impl Clone for Pattern {
             transform: self.transform.clone(),
             inverse: self.inverse.clone(),
             model: self.model.dynamic_clone(),
+            solid: self.solid,
         }
     }
 }
@@ -141,7 +185,7 @@ mod test {
         let mut shape = Shape::new(Sphere);
         shape.set_transform(scaling(2.0, 2.0, 2.0)).unwrap();
         let pattern = Pattern::new(TestPattern);
-        let c = pattern.at_shape(&shape, Point::new(2.0, 3.0, 4.0));
+        let c = pattern.at_shape(&shape, Point::new(2.0, 3.0, 4.0), 0.0);
         assert_eq!(c, Color::new(1.0, 1.5, 2.0));
     }
 
@@ -150,7 +194,7 @@ mod test {
         let shape = Shape::new(Sphere);
         let mut pattern = Pattern::new(TestPattern);
         pattern.set_transform(scaling(2.0, 2.0, 2.0)).unwrap();
-        let c = pattern.at_shape(&shape, Point::new(2.0, 3.0, 4.0));
+        let c = pattern.at_shape(&shape, Point::new(2.0, 3.0, 4.0), 0.0);
         assert_eq!(c, Color::new(1.0, 1.5, 2.0));
     }
 
@@ -160,7 +204,16 @@ mod test {
         shape.set_transform(scaling(2.0, 2.0, 2.0)).unwrap();
         let mut pattern = Pattern::new(TestPattern);
         pattern.set_transform(translation(0.5, 1.0, 1.5)).unwrap();
-        let c = pattern.at_shape(&shape, Point::new(2.5, 3.0, 3.5));
+        let c = pattern.at_shape(&shape, Point::new(2.5, 3.0, 3.5), 0.0);
         assert_eq!(c, Color::new(0.75, 0.5, 0.25));
     }
+
+    #[test]
+    fn pattern_moves_with_its_shape() {
+        let mut shape = Shape::new(Sphere);
+        shape.set_motion(translation(0.0, 4.0, 0.0)).unwrap();
+        let pattern = Pattern::new(TestPattern);
+        let c = pattern.at_shape(&shape, Point::new(0.0, 3.0, 0.0), 0.5);
+        assert_eq!(c, Color::new(0.0, 1.0, 0.0));
+    }
 }
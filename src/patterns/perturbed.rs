@@ -0,0 +1,74 @@
+use crate::{canvas::Color, noise::Perlin, Point};
+
+use super::{Pattern, PatternModel};
+
+/// Jitters points with gradient noise before handing them to another pattern,
+/// so its edges wobble instead of being perfectly straight.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Perturbed {
+    pub pattern: Pattern,
+    pub noise: Perlin,
+    pub scale: f64,
+}
+
+impl Perturbed {
+    pub fn new(pattern: Pattern, seed: u64, scale: f64) -> Self {
+        Perturbed {
+            pattern,
+            noise: Perlin::new(seed),
+            scale,
+        }
+    }
+}
+
+impl PatternModel for Perturbed {
+    fn at(&self, point: Point) -> Color {
+        let offset = self.noise.vector_noise(point) * self.scale;
+        self.pattern.at(point + offset)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        canvas::{BLACK, WHITE},
+        patterns::{Stripes, TestPattern},
+        transformations::translation,
+    };
+
+    use super::*;
+
+    #[test]
+    fn zero_scale_leaves_pattern_unchanged() {
+        let perturbed = Perturbed::new(Pattern::new(TestPattern), 1, 0.0);
+        let point = Point::new(0.3, 1.7, -2.2);
+        assert_eq!(perturbed.at(point), Color::new(0.3, 1.7, -2.2));
+    }
+
+    #[test]
+    fn inner_pattern_keeps_its_transform() {
+        let mut inner = Pattern::new(TestPattern);
+        inner.set_transform(translation(1.0, 2.0, 3.0)).unwrap();
+        let perturbed = Perturbed::new(inner, 1, 0.0);
+        let point = Point::new(1.0, 2.0, 3.0);
+        assert_eq!(perturbed.at(point), Color::new(0.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn perturbation_moves_points() {
+        let perturbed = Perturbed::new(Pattern::new(TestPattern), 1, 0.5);
+        let point = Point::new(0.3, 1.7, -2.2);
+        assert_ne!(perturbed.at(point), Color::new(0.3, 1.7, -2.2));
+    }
+
+    #[test]
+    fn perturbation_is_deterministic() {
+        let stripes = Pattern::new(Stripes::new(WHITE, BLACK));
+        let a = Perturbed::new(stripes.clone(), 7, 0.4);
+        let b = Perturbed::new(stripes, 7, 0.4);
+        for i in 0..50 {
+            let point = Point::new(i as f64 * 0.1, 0.3, 0.2);
+            assert_eq!(a.at(point), b.at(point));
+        }
+    }
+}
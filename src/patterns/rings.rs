@@ -1,25 +1,28 @@
 use crate::{canvas::Color, Point};
 
-use super::PatternModel;
+use super::{Pattern, PatternModel};
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct Rings {
-    a: Color,
-    b: Color,
+    a: Pattern,
+    b: Pattern,
 }
 
 impl Rings {
-    pub fn new(a: Color, b: Color) -> Self {
-        Rings { a, b }
+    pub fn new(a: impl Into<Pattern>, b: impl Into<Pattern>) -> Self {
+        Rings {
+            a: a.into(),
+            b: b.into(),
+        }
     }
 }
 
 impl PatternModel for Rings {
     fn at(&self, point: Point) -> Color {
         if (point.x * point.x + point.z * point.z).sqrt().floor() as i64 % 2 == 0 {
-            self.a
+            self.a.at(point)
         } else {
-            self.b
+            self.b.at(point)
         }
     }
 }
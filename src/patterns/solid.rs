@@ -0,0 +1,48 @@
+use crate::{canvas::Color, Point};
+
+use super::{Pattern, PatternModel};
+
+/// The same colour everywhere. Mostly useful as a leaf of a nested pattern.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Solid(pub Color);
+
+impl PatternModel for Solid {
+    fn at(&self, _point: Point) -> Color {
+        self.0
+    }
+}
+
+impl From<Color> for Pattern {
+    fn from(color: Color) -> Self {
+        Pattern::new(Solid(color))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::transformations::translation;
+
+    use super::*;
+
+    #[test]
+    fn solid_is_constant() {
+        let color = Color::new(0.2, 0.3, 0.4);
+        let solid = Solid(color);
+        assert_eq!(solid.at(Point::new(0.0, 0.0, 0.0)), color);
+        assert_eq!(solid.at(Point::new(-3.5, 2.0, 11.0)), color);
+    }
+
+    #[test]
+    fn solid_pattern_ignores_its_transform() {
+        let color = Color::new(0.2, 0.3, 0.4);
+        let mut pattern = Pattern::from(color);
+        pattern.set_transform(translation(0.5, 0.0, 0.0)).unwrap();
+        assert_eq!(pattern.at(Point::new(1.0, 2.0, 3.0)), color);
+    }
+
+    #[test]
+    fn pattern_from_color() {
+        let color = Color::new(0.2, 0.3, 0.4);
+        assert_eq!(Pattern::from(color), Pattern::new(Solid(color)));
+    }
+}
@@ -1,40 +1,57 @@
-use crate::{canvas::Color, Point};
+use crate::{
+    canvas::{Color, BLACK, WHITE},
+    Point,
+};
 
-use super::PatternModel;
+use super::{Pattern, PatternModel};
 
-#[derive(Default, Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct Stripes {
-    pub a: Color,
-    pub b: Color,
+    pub a: Pattern,
+    pub b: Pattern,
 }
 
 impl Stripes {
-    pub fn new(a: Color, b: Color) -> Self {
-        Stripes { a, b }
+    pub fn new(a: impl Into<Pattern>, b: impl Into<Pattern>) -> Self {
+        Stripes {
+            a: a.into(),
+            b: b.into(),
+        }
+    }
+}
+
+impl Default for Stripes {
+    fn default() -> Self {
+        Stripes::new(WHITE, BLACK)
     }
 }
 
 impl PatternModel for Stripes {
     fn at(&self, point: Point) -> Color {
         if point.x.floor() as i64 % 2 == 0 {
-            self.a
+            self.a.at(point)
         } else {
-            self.b
+            self.b.at(point)
         }
     }
 }
 
 #[cfg(test)]
 mod test {
-    use crate::canvas::{BLACK, WHITE};
+    use crate::transformations::scaling;
 
     use super::*;
 
     #[test]
     fn create_stripe_pattern() {
         let model = Stripes::new(WHITE, BLACK);
-        assert_eq!(model.a, WHITE);
-        assert_eq!(model.b, BLACK);
+        assert_eq!(model.a, Pattern::from(WHITE));
+        assert_eq!(model.b, Pattern::from(BLACK));
+    }
+
+    #[test]
+    fn default_stripes_are_white_and_black() {
+        assert_eq!(Stripes::default(), Stripes::new(WHITE, BLACK));
     }
 
     #[test]
@@ -63,4 +80,17 @@ mod test {
         assert_eq!(model.at(Point::new(-1.0, 0.0, 0.0)), BLACK);
         assert_eq!(model.at(Point::new(-1.1, 0.0, 0.0)), WHITE);
     }
+
+    #[test]
+    fn stripes_of_nested_patterns() {
+        let mut inner = Pattern::new(Stripes::new(WHITE, BLACK));
+        inner.set_transform(scaling(0.25, 1.0, 1.0)).unwrap();
+        let model = Stripes::new(inner, Color::new(0.5, 0.5, 0.5));
+        assert_eq!(model.at(Point::new(0.1, 0.0, 0.0)), WHITE);
+        assert_eq!(model.at(Point::new(0.3, 0.0, 0.0)), BLACK);
+        assert_eq!(
+            model.at(Point::new(1.3, 0.0, 0.0)),
+            Color::new(0.5, 0.5, 0.5)
+        );
+    }
 }
@@ -0,0 +1,55 @@
+use crate::{canvas::Color, noise::Perlin, Point};
+
+use super::PatternModel;
+
+/// Blends from `a` to `b` by the turbulence of the noise at each point.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Turbulence {
+    pub a: Color,
+    pub b: Color,
+    pub noise: Perlin,
+    pub octaves: usize,
+}
+
+impl Turbulence {
+    pub fn new(a: Color, b: Color, seed: u64) -> Self {
+        Turbulence {
+            a,
+            b,
+            noise: Perlin::new(seed),
+            octaves: 4,
+        }
+    }
+}
+
+impl PatternModel for Turbulence {
+    fn at(&self, point: Point) -> Color {
+        let fraction = self.noise.turbulence(point, self.octaves).clamp(0.0, 1.0);
+        self.a + (self.b - self.a) * fraction
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::canvas::{BLACK, WHITE};
+
+    use super::*;
+
+    #[test]
+    fn turbulence_is_a_on_lattice() {
+        let pattern = Turbulence::new(WHITE, BLACK, 3);
+        assert_eq!(pattern.at(Point::new(0.0, 0.0, 0.0)), WHITE);
+        assert_eq!(pattern.at(Point::new(2.0, -1.0, 4.0)), WHITE);
+    }
+
+    #[test]
+    fn turbulence_stays_between_colors() {
+        let pattern = Turbulence::new(BLACK, WHITE, 3);
+        for i in 0..100 {
+            let t = i as f64 * 0.173;
+            let color = pattern.at(Point::new(t, t * 0.3, -t));
+            assert_eq!(color, Color::new(color.red(), color.red(), color.red()));
+            assert!((0.0..=1.0).contains(&color.red()));
+        }
+    }
+}
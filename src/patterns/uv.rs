@@ -0,0 +1,371 @@
+use std::{f64::consts::PI, fmt::Debug};
+
+use crate::{canvas::Color, Point, Vector};
+
+use super::PatternModel;
+
+/// A pattern defined over the unit square rather than over 3D space.
+///
+/// `u` and `v` are expected to lie in `[0, 1]`. Use a [`TextureMap`] or a
+/// [`CubeMap`] to wrap one around a shape.
+pub trait UvPatternModel: Clone + Debug + PartialEq + 'static {
+    fn uv_at(&self, u: f64, v: f64) -> Color;
+}
+
+/// How a 3D pattern-space point is flattened onto the unit square.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum UvMapping {
+    Spherical,
+    Planar,
+    Cylindrical,
+    Cube,
+}
+
+impl UvMapping {
+    pub fn map(&self, point: Point) -> (f64, f64) {
+        match self {
+            UvMapping::Spherical => spherical_map(point),
+            UvMapping::Planar => planar_map(point),
+            UvMapping::Cylindrical => cylindrical_map(point),
+            UvMapping::Cube => {
+                let (_, u, v) = cube_map(point);
+                (u, v)
+            }
+        }
+    }
+}
+
+pub fn spherical_map(point: Point) -> (f64, f64) {
+    let theta = point.x.atan2(point.z);
+    let radius = (point - Point::new(0.0, 0.0, 0.0)).magnitude();
+    // The centre has no direction, so give it the middle of the map
+    if radius == 0.0 {
+        return (0.5, 0.5);
+    }
+    let phi = (point.y / radius).acos();
+
+    let raw_u = theta / (2.0 * PI);
+    let u = 1.0 - (raw_u + 0.5);
+    let v = 1.0 - phi / PI;
+    (u, v)
+}
+
+pub fn planar_map(point: Point) -> (f64, f64) {
+    (point.x.rem_euclid(1.0), point.z.rem_euclid(1.0))
+}
+
+pub fn cylindrical_map(point: Point) -> (f64, f64) {
+    let theta = point.x.atan2(point.z);
+    let raw_u = theta / (2.0 * PI);
+    let u = 1.0 - (raw_u + 0.5);
+    let v = point.y.rem_euclid(1.0);
+    (u, v)
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum CubeFace {
+    Left,
+    Right,
+    Front,
+    Back,
+    Up,
+    Down,
+}
+
+impl CubeFace {
+    pub fn from_point(point: Point) -> CubeFace {
+        let abs_x = point.x.abs();
+        let abs_y = point.y.abs();
+        let abs_z = point.z.abs();
+        let coord = abs_x.max(abs_y).max(abs_z);
+
+        if coord == point.x {
+            CubeFace::Right
+        } else if coord == -point.x {
+            CubeFace::Left
+        } else if coord == point.y {
+            CubeFace::Up
+        } else if coord == -point.y {
+            CubeFace::Down
+        } else if coord == point.z {
+            CubeFace::Front
+        } else {
+            CubeFace::Back
+        }
+    }
+
+    /// Same as [`CubeFace::from_point`], for looking up faces by direction.
+    pub fn from_direction(direction: Vector) -> CubeFace {
+        CubeFace::from_point(Point::new(direction.x, direction.y, direction.z))
+    }
+}
+
+/// Maps a point on the surface of the axis-aligned cube from -1 to 1 onto the
+/// face it lies on, with `u` and `v` measured across that face.
+pub fn cube_map(point: Point) -> (CubeFace, f64, f64) {
+    let face = CubeFace::from_point(point);
+    let wrap = |value: f64| value.rem_euclid(2.0) / 2.0;
+    let (u, v) = match face {
+        CubeFace::Front => (wrap(point.x + 1.0), wrap(point.y + 1.0)),
+        CubeFace::Back => (wrap(1.0 - point.x), wrap(point.y + 1.0)),
+        CubeFace::Left => (wrap(point.z + 1.0), wrap(point.y + 1.0)),
+        CubeFace::Right => (wrap(1.0 - point.z), wrap(point.y + 1.0)),
+        CubeFace::Up => (wrap(point.x + 1.0), wrap(1.0 - point.z)),
+        CubeFace::Down => (wrap(point.x + 1.0), wrap(point.z + 1.0)),
+    };
+    (face, u, v)
+}
+
+/// Evaluates a 2D pattern by flattening pattern space with a [`UvMapping`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextureMap<P: UvPatternModel> {
+    pub pattern: P,
+    pub mapping: UvMapping,
+}
+
+impl<P: UvPatternModel> TextureMap<P> {
+    pub fn new(pattern: P, mapping: UvMapping) -> Self {
+        TextureMap { pattern, mapping }
+    }
+}
+
+impl<P: UvPatternModel> PatternModel for TextureMap<P> {
+    fn at(&self, point: Point) -> Color {
+        let (u, v) = self.mapping.map(point);
+        self.pattern.uv_at(u, v)
+    }
+}
+
+/// Like a [`TextureMap`] with [`UvMapping::Cube`], but with a separate 2D
+/// pattern for each face.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CubeMap<P: UvPatternModel> {
+    pub left: P,
+    pub front: P,
+    pub right: P,
+    pub back: P,
+    pub up: P,
+    pub down: P,
+}
+
+impl<P: UvPatternModel> CubeMap<P> {
+    pub fn new(left: P, front: P, right: P, back: P, up: P, down: P) -> Self {
+        CubeMap {
+            left,
+            front,
+            right,
+            back,
+            up,
+            down,
+        }
+    }
+
+    pub fn face(&self, face: CubeFace) -> &P {
+        match face {
+            CubeFace::Left => &self.left,
+            CubeFace::Right => &self.right,
+            CubeFace::Front => &self.front,
+            CubeFace::Back => &self.back,
+            CubeFace::Up => &self.up,
+            CubeFace::Down => &self.down,
+        }
+    }
+}
+
+impl<P: UvPatternModel> PatternModel for CubeMap<P> {
+    fn at(&self, point: Point) -> Color {
+        let (face, u, v) = cube_map(point);
+        self.face(face).uv_at(u, v)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::f64::consts::FRAC_1_SQRT_2;
+
+    use crate::{
+        canvas::{BLACK, WHITE},
+        patterns::{AlignCheck, UvCheckers},
+        EQUALITY_EPSILON,
+    };
+
+    use super::*;
+
+    #[test]
+    fn spherical_mapping_on_3d_point() {
+        let examples = [
+            (Point::new(0.0, 0.0, -1.0), 0.0, 0.5),
+            (Point::new(1.0, 0.0, 0.0), 0.25, 0.5),
+            (Point::new(0.0, 0.0, 1.0), 0.5, 0.5),
+            (Point::new(-1.0, 0.0, 0.0), 0.75, 0.5),
+            (Point::new(0.0, 1.0, 0.0), 0.5, 1.0),
+            (Point::new(0.0, -1.0, 0.0), 0.5, 0.0),
+            (Point::new(FRAC_1_SQRT_2, FRAC_1_SQRT_2, 0.0), 0.25, 0.75),
+        ];
+        for (point, u, v) in examples {
+            let (actual_u, actual_v) = spherical_map(point);
+            assert!((actual_u - u).abs() < EQUALITY_EPSILON, "{point:?}");
+            assert!((actual_v - v).abs() < EQUALITY_EPSILON, "{point:?}");
+        }
+    }
+
+    #[test]
+    fn spherical_mapping_at_the_centre() {
+        assert_eq!(spherical_map(Point::new(0.0, 0.0, 0.0)), (0.5, 0.5));
+    }
+
+    #[test]
+    fn planar_mapping_on_3d_point() {
+        let examples = [
+            (Point::new(0.25, 0.0, 0.5), 0.25, 0.5),
+            (Point::new(0.25, 0.0, -0.25), 0.25, 0.75),
+            (Point::new(0.25, 0.5, -0.25), 0.25, 0.75),
+            (Point::new(1.25, 0.0, 0.5), 0.25, 0.5),
+            (Point::new(0.25, 0.0, -1.75), 0.25, 0.25),
+            (Point::new(1.0, 0.0, -1.0), 0.0, 0.0),
+            (Point::new(0.0, 0.0, 0.0), 0.0, 0.0),
+        ];
+        for (point, u, v) in examples {
+            let (actual_u, actual_v) = planar_map(point);
+            assert!((actual_u - u).abs() < EQUALITY_EPSILON, "{point:?}");
+            assert!((actual_v - v).abs() < EQUALITY_EPSILON, "{point:?}");
+        }
+    }
+
+    #[test]
+    fn cylindrical_mapping_on_3d_point() {
+        let examples = [
+            (Point::new(0.0, 0.0, -1.0), 0.0, 0.0),
+            (Point::new(0.0, 0.5, -1.0), 0.0, 0.5),
+            (Point::new(0.0, 1.0, -1.0), 0.0, 0.0),
+            (Point::new(FRAC_1_SQRT_2, 0.5, -FRAC_1_SQRT_2), 0.125, 0.5),
+            (Point::new(1.0, 0.5, 0.0), 0.25, 0.5),
+            (Point::new(FRAC_1_SQRT_2, 0.5, FRAC_1_SQRT_2), 0.375, 0.5),
+            (Point::new(0.0, -0.25, 1.0), 0.5, 0.75),
+            (Point::new(-FRAC_1_SQRT_2, 0.5, FRAC_1_SQRT_2), 0.625, 0.5),
+            (Point::new(-1.0, 1.25, 0.0), 0.75, 0.25),
+            (Point::new(-FRAC_1_SQRT_2, 0.5, -FRAC_1_SQRT_2), 0.875, 0.5),
+        ];
+        for (point, u, v) in examples {
+            let (actual_u, actual_v) = cylindrical_map(point);
+            assert!((actual_u - u).abs() < EQUALITY_EPSILON, "{point:?}");
+            assert!((actual_v - v).abs() < EQUALITY_EPSILON, "{point:?}");
+        }
+    }
+
+    #[test]
+    fn identify_cube_face() {
+        let examples = [
+            (Point::new(-1.0, 0.5, -0.25), CubeFace::Left),
+            (Point::new(1.1, -0.75, 0.8), CubeFace::Right),
+            (Point::new(0.1, 0.6, 0.9), CubeFace::Front),
+            (Point::new(-0.7, 0.0, -2.0), CubeFace::Back),
+            (Point::new(0.5, 1.0, 0.9), CubeFace::Up),
+            (Point::new(-0.2, -1.3, 1.1), CubeFace::Down),
+        ];
+        for (point, face) in examples {
+            assert_eq!(CubeFace::from_point(point), face);
+        }
+    }
+
+    #[test]
+    fn uv_mapping_on_cube_faces() {
+        let examples = [
+            (Point::new(-0.5, 0.5, 1.0), CubeFace::Front, 0.25, 0.75),
+            (Point::new(0.5, -0.5, 1.0), CubeFace::Front, 0.75, 0.25),
+            (Point::new(0.5, 0.5, -1.0), CubeFace::Back, 0.25, 0.75),
+            (Point::new(-0.5, -0.5, -1.0), CubeFace::Back, 0.75, 0.25),
+            (Point::new(-1.0, 0.5, -0.5), CubeFace::Left, 0.25, 0.75),
+            (Point::new(-1.0, -0.5, 0.5), CubeFace::Left, 0.75, 0.25),
+            (Point::new(1.0, 0.5, 0.5), CubeFace::Right, 0.25, 0.75),
+            (Point::new(1.0, -0.5, -0.5), CubeFace::Right, 0.75, 0.25),
+            (Point::new(-0.5, 1.0, -0.5), CubeFace::Up, 0.25, 0.75),
+            (Point::new(0.5, 1.0, 0.5), CubeFace::Up, 0.75, 0.25),
+            (Point::new(-0.5, -1.0, 0.5), CubeFace::Down, 0.25, 0.75),
+            (Point::new(0.5, -1.0, -0.5), CubeFace::Down, 0.75, 0.25),
+        ];
+        for (point, face, u, v) in examples {
+            let (actual_face, actual_u, actual_v) = cube_map(point);
+            assert_eq!(actual_face, face);
+            assert!((actual_u - u).abs() < EQUALITY_EPSILON, "{point:?}");
+            assert!((actual_v - v).abs() < EQUALITY_EPSILON, "{point:?}");
+        }
+    }
+
+    #[test]
+    fn texture_map_with_spherical_map() {
+        let checkers = UvCheckers::new(16.0, 8.0, BLACK, WHITE);
+        let pattern = TextureMap::new(checkers, UvMapping::Spherical);
+        let examples = [
+            (Point::new(0.4315, 0.4670, 0.7719), WHITE),
+            (Point::new(-0.9654, 0.2552, -0.0534), BLACK),
+            (Point::new(0.1039, 0.7090, 0.6975), WHITE),
+            (Point::new(-0.4986, -0.7856, -0.3663), BLACK),
+            (Point::new(-0.0317, -0.9395, 0.3411), BLACK),
+            (Point::new(0.4809, -0.7721, 0.4154), BLACK),
+            (Point::new(0.0285, -0.9612, -0.2745), BLACK),
+            (Point::new(-0.5734, -0.2162, -0.7903), WHITE),
+            (Point::new(0.7688, -0.1470, 0.6223), BLACK),
+            (Point::new(-0.7652, 0.2175, 0.6060), BLACK),
+        ];
+        for (point, color) in examples {
+            assert_eq!(pattern.at(point), color, "{point:?}");
+        }
+    }
+
+    #[test]
+    fn cube_map_finds_colors_on_each_face() {
+        let red = Color::new(1.0, 0.0, 0.0);
+        let yellow = Color::new(1.0, 1.0, 0.0);
+        let brown = Color::new(1.0, 0.5, 0.0);
+        let green = Color::new(0.0, 1.0, 0.0);
+        let cyan = Color::new(0.0, 1.0, 1.0);
+        let blue = Color::new(0.0, 0.0, 1.0);
+        let purple = Color::new(1.0, 0.0, 1.0);
+        let white = WHITE;
+
+        let left = AlignCheck::new(yellow, cyan, red, blue, brown);
+        let front = AlignCheck::new(cyan, red, yellow, brown, green);
+        let right = AlignCheck::new(red, yellow, purple, green, white);
+        let back = AlignCheck::new(green, purple, cyan, white, blue);
+        let up = AlignCheck::new(brown, cyan, purple, red, yellow);
+        let down = AlignCheck::new(purple, brown, green, blue, white);
+        let pattern = CubeMap::new(left, front, right, back, up, down);
+
+        let examples = [
+            (Point::new(-1.0, 0.0, 0.0), yellow),
+            (Point::new(-1.0, 0.9, -0.9), cyan),
+            (Point::new(-1.0, 0.9, 0.9), red),
+            (Point::new(-1.0, -0.9, -0.9), blue),
+            (Point::new(-1.0, -0.9, 0.9), brown),
+            (Point::new(0.0, 0.0, 1.0), cyan),
+            (Point::new(-0.9, 0.9, 1.0), red),
+            (Point::new(0.9, 0.9, 1.0), yellow),
+            (Point::new(-0.9, -0.9, 1.0), brown),
+            (Point::new(0.9, -0.9, 1.0), green),
+            (Point::new(1.0, 0.0, 0.0), red),
+            (Point::new(1.0, 0.9, 0.9), yellow),
+            (Point::new(1.0, 0.9, -0.9), purple),
+            (Point::new(1.0, -0.9, 0.9), green),
+            (Point::new(1.0, -0.9, -0.9), white),
+            (Point::new(0.0, 0.0, -1.0), green),
+            (Point::new(0.9, 0.9, -1.0), purple),
+            (Point::new(-0.9, 0.9, -1.0), cyan),
+            (Point::new(0.9, -0.9, -1.0), white),
+            (Point::new(-0.9, -0.9, -1.0), blue),
+            (Point::new(0.0, 1.0, 0.0), brown),
+            (Point::new(-0.9, 1.0, -0.9), cyan),
+            (Point::new(0.9, 1.0, -0.9), purple),
+            (Point::new(-0.9, 1.0, 0.9), red),
+            (Point::new(0.9, 1.0, 0.9), yellow),
+            (Point::new(0.0, -1.0, 0.0), purple),
+            (Point::new(-0.9, -1.0, 0.9), brown),
+            (Point::new(0.9, -1.0, 0.9), green),
+            (Point::new(-0.9, -1.0, -0.9), blue),
+            (Point::new(0.9, -1.0, -0.9), white),
+        ];
+        for (point, color) in examples {
+            assert_eq!(pattern.at(point), color, "{point:?}");
+        }
+    }
+}
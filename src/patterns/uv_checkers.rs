@@ -0,0 +1,51 @@
+use crate::canvas::Color;
+
+use super::UvPatternModel;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct UvCheckers {
+    pub width: f64,
+    pub height: f64,
+    pub a: Color,
+    pub b: Color,
+}
+
+impl UvCheckers {
+    pub fn new(width: f64, height: f64, a: Color, b: Color) -> Self {
+        UvCheckers {
+            width,
+            height,
+            a,
+            b,
+        }
+    }
+}
+
+impl UvPatternModel for UvCheckers {
+    fn uv_at(&self, u: f64, v: f64) -> Color {
+        let u2 = (u * self.width).floor() as i64;
+        let v2 = (v * self.height).floor() as i64;
+        if (u2 + v2).rem_euclid(2) == 0 {
+            self.a
+        } else {
+            self.b
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::canvas::{BLACK, WHITE};
+
+    use super::*;
+
+    #[test]
+    fn checkers_in_2d() {
+        let checkers = UvCheckers::new(2.0, 2.0, BLACK, WHITE);
+        assert_eq!(checkers.uv_at(0.0, 0.0), BLACK);
+        assert_eq!(checkers.uv_at(0.5, 0.0), WHITE);
+        assert_eq!(checkers.uv_at(0.0, 0.5), WHITE);
+        assert_eq!(checkers.uv_at(0.5, 0.5), BLACK);
+        assert_eq!(checkers.uv_at(1.0, 1.0), BLACK);
+    }
+}
@@ -0,0 +1,68 @@
+use crate::canvas::{Canvas, Color};
+
+use super::UvPatternModel;
+
+/// A 2D pattern that looks up colours in an image, with `v = 1` at the top.
+#[derive(Debug, Clone, PartialEq)]
+pub struct UvImage {
+    pub canvas: Canvas,
+}
+
+impl UvImage {
+    pub fn new(canvas: Canvas) -> Self {
+        UvImage { canvas }
+    }
+}
+
+impl UvPatternModel for UvImage {
+    fn uv_at(&self, u: f64, v: f64) -> Color {
+        let width = self.canvas.width();
+        let height = self.canvas.height();
+        if width == 0 || height == 0 {
+            return Color::default();
+        }
+
+        let v = 1.0 - v;
+        let x = (u * (width - 1) as f64)
+            .round()
+            .clamp(0.0, (width - 1) as f64);
+        let y = (v * (height - 1) as f64)
+            .round()
+            .clamp(0.0, (height - 1) as f64);
+        self.canvas
+            .pixel_at(x as usize, y as usize)
+            .expect("pixel out of bounds")
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn image_pattern_checkers() {
+        let ppm = "P3\n\
+            10 10\n\
+            10\n\
+            0 0 0  1 1 1  2 2 2  3 3 3  4 4 4  5 5 5  6 6 6  7 7 7  8 8 8  9 9 9\n\
+            1 1 1  2 2 2  3 3 3  4 4 4  5 5 5  6 6 6  7 7 7  8 8 8  9 9 9  0 0 0\n\
+            2 2 2  3 3 3  4 4 4  5 5 5  6 6 6  7 7 7  8 8 8  9 9 9  0 0 0  1 1 1\n\
+            3 3 3  4 4 4  5 5 5  6 6 6  7 7 7  8 8 8  9 9 9  0 0 0  1 1 1  2 2 2\n\
+            4 4 4  5 5 5  6 6 6  7 7 7  8 8 8  9 9 9  0 0 0  1 1 1  2 2 2  3 3 3\n\
+            5 5 5  6 6 6  7 7 7  8 8 8  9 9 9  0 0 0  1 1 1  2 2 2  3 3 3  4 4 4\n\
+            6 6 6  7 7 7  8 8 8  9 9 9  0 0 0  1 1 1  2 2 2  3 3 3  4 4 4  5 5 5\n\
+            7 7 7  8 8 8  9 9 9  0 0 0  1 1 1  2 2 2  3 3 3  4 4 4  5 5 5  6 6 6\n\
+            8 8 8  9 9 9  0 0 0  1 1 1  2 2 2  3 3 3  4 4 4  5 5 5  6 6 6  7 7 7\n\
+            9 9 9  0 0 0  1 1 1  2 2 2  3 3 3  4 4 4  5 5 5  6 6 6  7 7 7  8 8 8\n";
+        let pattern = UvImage::new(Canvas::from_ppm(ppm).unwrap());
+        let examples = [
+            (0.0, 0.0, Color::new(0.9, 0.9, 0.9)),
+            (0.3, 0.0, Color::new(0.2, 0.2, 0.2)),
+            (0.6, 0.3, Color::new(0.1, 0.1, 0.1)),
+            (1.0, 1.0, Color::new(0.9, 0.9, 0.9)),
+        ];
+        for (u, v, color) in examples {
+            assert_eq!(pattern.uv_at(u, v), color, "({u}, {v})");
+        }
+    }
+}
@@ -0,0 +1,67 @@
+use crate::{canvas::Color, noise::Perlin, Point};
+
+use super::PatternModel;
+
+/// Growth rings around the y axis, like [`super::Rings`], distorted by
+/// turbulence and blended rather than banded.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Wood {
+    pub a: Color,
+    pub b: Color,
+    pub noise: Perlin,
+    pub octaves: usize,
+    pub strength: f64,
+}
+
+impl Wood {
+    pub fn new(a: Color, b: Color, seed: u64) -> Self {
+        Wood {
+            a,
+            b,
+            noise: Perlin::new(seed),
+            octaves: 2,
+            strength: 0.5,
+        }
+    }
+}
+
+impl PatternModel for Wood {
+    fn at(&self, point: Point) -> Color {
+        let turbulence = self.noise.turbulence(point, self.octaves);
+        let distance = (point.x * point.x + point.z * point.z).sqrt();
+        let rings = distance + self.strength * turbulence;
+        let fraction = rings - rings.floor();
+        self.a + (self.b - self.a) * fraction
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::canvas::{BLACK, WHITE};
+
+    use super::*;
+
+    #[test]
+    fn wood_without_turbulence_is_rings() {
+        let mut wood = Wood::new(BLACK, WHITE, 0);
+        wood.strength = 0.0;
+        assert_eq!(wood.at(Point::new(0.0, 0.0, 0.0)), BLACK);
+        assert_eq!(
+            wood.at(Point::new(0.25, 5.0, 0.0)),
+            Color::new(0.25, 0.25, 0.25)
+        );
+        assert_eq!(
+            wood.at(Point::new(0.0, -2.0, 1.5)),
+            Color::new(0.5, 0.5, 0.5)
+        );
+    }
+
+    #[test]
+    fn turbulence_distorts_wood() {
+        let mut plain = Wood::new(BLACK, WHITE, 11);
+        plain.strength = 0.0;
+        let wood = Wood::new(BLACK, WHITE, 11);
+        let point = Point::new(0.37, 0.52, 0.61);
+        assert_ne!(plain.at(point), wood.at(point));
+    }
+}
@@ -0,0 +1,96 @@
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+/// How far a render has got.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub struct Progress {
+    pub rows_done: usize,
+    pub rows: usize,
+    pub elapsed: Duration,
+}
+
+impl Progress {
+    pub fn fraction(&self) -> f64 {
+        if self.rows == 0 {
+            return 1.0;
+        }
+        self.rows_done as f64 / self.rows as f64
+    }
+
+    /// How much longer the render should take if the remaining rows go as
+    /// fast as the ones so far. Unknown until a row is done.
+    pub fn eta(&self) -> Option<Duration> {
+        if self.rows_done == 0 {
+            return None;
+        }
+        let remaining = self.rows.saturating_sub(self.rows_done) as u32;
+        Some(self.elapsed / self.rows_done as u32 * remaining)
+    }
+}
+
+/// Asks a render to stop. Clones share the same flag, so one can be handed to
+/// another thread while the render watches the other.
+#[derive(Debug, Default, Clone)]
+pub struct CancellationToken {
+    cancelled: Arc<AtomicBool>,
+}
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        CancellationToken::default()
+    }
+
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+}
+
+#[derive(Default, Debug, Copy, Clone, PartialEq, Eq)]
+pub struct CancelledError;
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn fraction_done() {
+        let progress = Progress {
+            rows_done: 3,
+            rows: 12,
+            elapsed: Duration::from_secs(6),
+        };
+        assert_eq!(progress.fraction(), 0.25);
+        assert_eq!(Progress::default().fraction(), 1.0);
+    }
+
+    #[test]
+    fn eta_extrapolates_from_rows_done() {
+        let mut progress = Progress {
+            rows_done: 0,
+            rows: 12,
+            elapsed: Duration::from_secs(1),
+        };
+        assert_eq!(progress.eta(), None);
+        progress.rows_done = 3;
+        progress.elapsed = Duration::from_secs(6);
+        assert_eq!(progress.eta(), Some(Duration::from_secs(18)));
+    }
+
+    #[test]
+    fn cancelling_is_shared_between_clones() {
+        let token = CancellationToken::new();
+        let other = token.clone();
+        assert!(!token.is_cancelled());
+        other.cancel();
+        assert!(token.is_cancelled());
+    }
+}
@@ -0,0 +1,298 @@
+use crate::{
+    matrices::{Matrix, Transform},
+    Vector, EQUALITY_EPSILON,
+};
+
+// Above this, slerp's sine gets too small to divide by, and a normalized lerp
+// is indistinguishable
+const SLERP_THRESHOLD: f64 = 0.9995;
+
+/// A rotation, stored as a unit quaternion `w + xi + yj + zk`.
+#[derive(Debug, Copy, Clone)]
+pub struct Quaternion {
+    pub w: f64,
+    pub x: f64,
+    pub y: f64,
+    pub z: f64,
+}
+
+impl Quaternion {
+    pub fn new(w: f64, x: f64, y: f64, z: f64) -> Self {
+        Quaternion { w, x, y, z }
+    }
+
+    pub fn identity() -> Self {
+        Quaternion::new(1.0, 0.0, 0.0, 0.0)
+    }
+
+    /// Rotates by `angle` radians around `axis`, which needn't be normalized.
+    pub fn from_axis_angle(axis: Vector, angle: f64) -> Self {
+        let axis = axis.normalize();
+        let (sin, cos) = (angle / 2.0).sin_cos();
+        Quaternion::new(cos, axis.x * sin, axis.y * sin, axis.z * sin)
+    }
+
+    /// The rotation described by a 3x3 orthonormal matrix with a determinant
+    /// of 1.
+    pub fn from_rotation(m: &Matrix<3, 3>) -> Self {
+        let trace = m[[0, 0]] + m[[1, 1]] + m[[2, 2]];
+        // Divides by the largest component to stay numerically stable
+        let q = if trace > 0.0 {
+            let s = (trace + 1.0).sqrt() * 2.0;
+            Quaternion::new(
+                s / 4.0,
+                (m[[2, 1]] - m[[1, 2]]) / s,
+                (m[[0, 2]] - m[[2, 0]]) / s,
+                (m[[1, 0]] - m[[0, 1]]) / s,
+            )
+        } else if m[[0, 0]] > m[[1, 1]] && m[[0, 0]] > m[[2, 2]] {
+            let s = (1.0 + m[[0, 0]] - m[[1, 1]] - m[[2, 2]]).sqrt() * 2.0;
+            Quaternion::new(
+                (m[[2, 1]] - m[[1, 2]]) / s,
+                s / 4.0,
+                (m[[0, 1]] + m[[1, 0]]) / s,
+                (m[[0, 2]] + m[[2, 0]]) / s,
+            )
+        } else if m[[1, 1]] > m[[2, 2]] {
+            let s = (1.0 + m[[1, 1]] - m[[0, 0]] - m[[2, 2]]).sqrt() * 2.0;
+            Quaternion::new(
+                (m[[0, 2]] - m[[2, 0]]) / s,
+                (m[[0, 1]] + m[[1, 0]]) / s,
+                s / 4.0,
+                (m[[1, 2]] + m[[2, 1]]) / s,
+            )
+        } else {
+            let s = (1.0 + m[[2, 2]] - m[[0, 0]] - m[[1, 1]]).sqrt() * 2.0;
+            Quaternion::new(
+                (m[[1, 0]] - m[[0, 1]]) / s,
+                (m[[0, 2]] + m[[2, 0]]) / s,
+                (m[[1, 2]] + m[[2, 1]]) / s,
+                s / 4.0,
+            )
+        };
+        q.normalize()
+    }
+
+    /// The normalized axis and the angle in radians around it. A rotation by
+    /// nothing reports the x axis.
+    pub fn axis_angle(&self) -> (Vector, f64) {
+        let q = self.normalize();
+        let angle = 2.0 * q.w.clamp(-1.0, 1.0).acos();
+        let sin = (1.0 - q.w * q.w).max(0.0).sqrt();
+        if sin < EQUALITY_EPSILON {
+            return (Vector::new(1.0, 0.0, 0.0), 0.0);
+        }
+        (Vector::new(q.x / sin, q.y / sin, q.z / sin), angle)
+    }
+
+    pub fn magnitude(&self) -> f64 {
+        Quaternion::dot(*self, *self).sqrt()
+    }
+
+    pub fn normalize(&self) -> Quaternion {
+        let magnitude = self.magnitude();
+        Quaternion::new(
+            self.w / magnitude,
+            self.x / magnitude,
+            self.y / magnitude,
+            self.z / magnitude,
+        )
+    }
+
+    /// The opposite rotation, for a unit quaternion.
+    pub fn conjugate(&self) -> Quaternion {
+        Quaternion::new(self.w, -self.x, -self.y, -self.z)
+    }
+
+    pub fn dot(a: Quaternion, b: Quaternion) -> f64 {
+        a.w * b.w + a.x * b.x + a.y * b.y + a.z * b.z
+    }
+
+    pub fn rotate(&self, v: Vector) -> Vector {
+        let rotated = *self * Quaternion::new(0.0, v.x, v.y, v.z) * self.conjugate();
+        Vector::new(rotated.x, rotated.y, rotated.z)
+    }
+
+    /// Turns at a constant rate from `self` at `t = 0` to `other` at `t = 1`,
+    /// the short way round.
+    pub fn slerp(&self, other: Quaternion, t: f64) -> Quaternion {
+        let mut other = other;
+        let mut cos = Quaternion::dot(*self, other);
+        // q and -q are the same rotation; pick whichever is closer
+        if cos < 0.0 {
+            other = Quaternion::new(-other.w, -other.x, -other.y, -other.z);
+            cos = -cos;
+        }
+
+        let (a, b) = if cos > SLERP_THRESHOLD {
+            (1.0 - t, t)
+        } else {
+            let theta = cos.acos();
+            let sin = theta.sin();
+            (((1.0 - t) * theta).sin() / sin, (t * theta).sin() / sin)
+        };
+        Quaternion::new(
+            a * self.w + b * other.w,
+            a * self.x + b * other.x,
+            a * self.y + b * other.y,
+            a * self.z + b * other.z,
+        )
+        .normalize()
+    }
+
+    pub fn to_transform(&self) -> Transform {
+        let Quaternion { w, x, y, z } = self.normalize();
+        Transform::new([
+            [
+                1.0 - 2.0 * (y * y + z * z),
+                2.0 * (x * y - w * z),
+                2.0 * (x * z + w * y),
+                0.0,
+            ],
+            [
+                2.0 * (x * y + w * z),
+                1.0 - 2.0 * (x * x + z * z),
+                2.0 * (y * z - w * x),
+                0.0,
+            ],
+            [
+                2.0 * (x * z - w * y),
+                2.0 * (y * z + w * x),
+                1.0 - 2.0 * (x * x + y * y),
+                0.0,
+            ],
+            [0.0, 0.0, 0.0, 1.0],
+        ])
+        .expect("casting transform")
+    }
+}
+
+impl Default for Quaternion {
+    fn default() -> Self {
+        Quaternion::identity()
+    }
+}
+
+impl PartialEq for Quaternion {
+    fn eq(&self, other: &Self) -> bool {
+        (self.w - other.w).abs() < EQUALITY_EPSILON
+            && (self.x - other.x).abs() < EQUALITY_EPSILON
+            && (self.y - other.y).abs() < EQUALITY_EPSILON
+            && (self.z - other.z).abs() < EQUALITY_EPSILON
+    }
+}
+
+/// Composes rotations: `a * b` rotates by `b`, then by `a`.
+impl std::ops::Mul for Quaternion {
+    type Output = Quaternion;
+
+    fn mul(self, rhs: Self) -> Self::Output {
+        Quaternion::new(
+            self.w * rhs.w - self.x * rhs.x - self.y * rhs.y - self.z * rhs.z,
+            self.w * rhs.x + self.x * rhs.w + self.y * rhs.z - self.z * rhs.y,
+            self.w * rhs.y - self.x * rhs.z + self.y * rhs.w + self.z * rhs.x,
+            self.w * rhs.z + self.x * rhs.y - self.y * rhs.x + self.z * rhs.w,
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::f64::consts::PI;
+
+    use crate::transformations::{rotation_x, rotation_y, rotation_z, Builder};
+
+    use super::*;
+
+    #[test]
+    fn axis_angle_matches_axis_rotations() {
+        let x = Vector::new(1.0, 0.0, 0.0);
+        let y = Vector::new(0.0, 1.0, 0.0);
+        let z = Vector::new(0.0, 0.0, 2.0);
+        assert_eq!(
+            Quaternion::from_axis_angle(x, PI / 3.0).to_transform(),
+            rotation_x(PI / 3.0)
+        );
+        assert_eq!(
+            Quaternion::from_axis_angle(y, -PI / 4.0).to_transform(),
+            rotation_y(-PI / 4.0)
+        );
+        assert_eq!(
+            Quaternion::from_axis_angle(z, PI).to_transform(),
+            rotation_z(PI)
+        );
+    }
+
+    #[test]
+    fn axis_angle_round_trips() {
+        let axis = Vector::new(1.0, 2.0, -2.0);
+        let (found_axis, angle) = Quaternion::from_axis_angle(axis, 1.2).axis_angle();
+        assert_eq!(found_axis, axis.normalize());
+        assert!((angle - 1.2).abs() < EQUALITY_EPSILON);
+        assert_eq!(
+            Quaternion::identity().axis_angle(),
+            (Vector::new(1.0, 0.0, 0.0), 0.0)
+        );
+    }
+
+    #[test]
+    fn rotating_a_vector() {
+        let q = Quaternion::from_axis_angle(Vector::new(0.0, 0.0, 1.0), PI / 2.0);
+        assert_eq!(
+            q.rotate(Vector::new(1.0, 0.0, 0.0)),
+            Vector::new(0.0, 1.0, 0.0)
+        );
+    }
+
+    #[test]
+    fn multiplying_composes_rotations() {
+        let a = Quaternion::from_axis_angle(Vector::new(1.0, 0.0, 0.0), PI / 2.0);
+        let b = Quaternion::from_axis_angle(Vector::new(0.0, 1.0, 0.0), PI / 3.0);
+        let expected = Builder::new()
+            .rotation_y(PI / 3.0)
+            .rotation_x(PI / 2.0)
+            .transform();
+        assert_eq!((a * b).to_transform(), expected);
+        assert_eq!(a * a.conjugate(), Quaternion::identity());
+    }
+
+    #[test]
+    fn recovering_rotation_from_matrix() {
+        let rotations = [
+            Builder::new()
+                .rotation_x(0.3)
+                .rotation_y(2.0)
+                .rotation_z(-1.0)
+                .transform(),
+            rotation_x(PI),
+            rotation_y(PI),
+            rotation_z(PI),
+        ];
+        for rotation in rotations {
+            let m = rotation.submatrix(3, 3).unwrap();
+            assert_eq!(Quaternion::from_rotation(&m).to_transform(), rotation);
+        }
+    }
+
+    #[test]
+    fn slerp_turns_at_a_constant_rate() {
+        let axis = Vector::new(0.0, 1.0, 0.0);
+        let a = Quaternion::identity();
+        let b = Quaternion::from_axis_angle(axis, PI / 2.0);
+        assert_eq!(a.slerp(b, 0.0), a);
+        assert_eq!(a.slerp(b, 1.0), b);
+        assert_eq!(
+            a.slerp(b, 0.25),
+            Quaternion::from_axis_angle(axis, PI / 8.0)
+        );
+    }
+
+    #[test]
+    fn slerp_takes_the_short_way_round() {
+        let axis = Vector::new(0.0, 0.0, 1.0);
+        let a = Quaternion::from_axis_angle(axis, -0.1);
+        let b = Quaternion::from_axis_angle(axis, 2.0 * PI - 0.1);
+        let halfway = a.slerp(b, 0.5).to_transform();
+        assert_eq!(halfway, rotation_z(-0.1));
+    }
+}
@@ -0,0 +1,197 @@
+use std::{cell::RefCell, f64::consts::PI};
+
+use crate::Vector;
+
+/// A small, seedable pseudo-random number generator (SplitMix64).
+///
+/// Renders need to be reproducible, so everything random in the tracer is
+/// driven from one of these rather than from an OS source.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        Rng { state: seed }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// Returns a number in `[0, 1)`.
+    pub fn next_f64(&mut self) -> f64 {
+        // Use the top 53 bits so every value is exactly representable
+        (self.next_u64() >> 11) as f64 / (1_u64 << 53) as f64
+    }
+
+    /// Returns an index in `0..bound`.
+    pub fn below(&mut self, bound: usize) -> usize {
+        (self.next_f64() * bound as f64) as usize
+    }
+}
+
+impl Default for Rng {
+    fn default() -> Self {
+        Rng::new(0)
+    }
+}
+
+thread_local! {
+    static THREAD_RNG: RefCell<Rng> = RefCell::new(Rng::default());
+}
+
+/// Restarts this thread's generator, which drives all of the sampling below.
+///
+/// Rendering calls this with the camera's seed, so renders are repeatable.
+pub fn seed(seed: u64) {
+    THREAD_RNG.with(|rng| *rng.borrow_mut() = Rng::new(seed));
+}
+
+/// Returns a number in `[0, 1)` from this thread's generator.
+pub fn random() -> f64 {
+    THREAD_RNG.with(|rng| rng.borrow_mut().next_f64())
+}
+
+/// Picks a direction in the hemisphere around `normal`, favouring directions
+/// close to it in proportion to the cosine of the angle between them.
+pub fn cosine_weighted_hemisphere(normal: Vector) -> Vector {
+    let r = random().sqrt();
+    let theta = 2.0 * PI * random();
+    let x = r * theta.cos();
+    let y = r * theta.sin();
+    let z = (1.0 - r * r).max(0.0).sqrt();
+
+    let (tangent, bitangent) = orthonormal_basis(normal);
+    (tangent * x + bitangent * y + normal * z).normalize()
+}
+
+/// Picks a point inside the unit sphere, as a vector from its centre.
+pub fn in_unit_sphere() -> Vector {
+    loop {
+        let candidate = Vector::new(
+            2.0 * random() - 1.0,
+            2.0 * random() - 1.0,
+            2.0 * random() - 1.0,
+        );
+        if candidate.magnitude() < 1.0 {
+            return candidate;
+        }
+    }
+}
+
+/// Picks a point inside the unit disk, as `(x, y)`.
+pub fn in_unit_disk() -> (f64, f64) {
+    let r = random().sqrt();
+    let theta = 2.0 * PI * random();
+    (r * theta.cos(), r * theta.sin())
+}
+
+/// Returns two unit vectors perpendicular to `normal` and each other.
+pub fn orthonormal_basis(normal: Vector) -> (Vector, Vector) {
+    let helper = if normal.x.abs() > 0.9 {
+        Vector::new(0.0, 1.0, 0.0)
+    } else {
+        Vector::new(1.0, 0.0, 0.0)
+    };
+    let tangent = Vector::cross(helper, normal).normalize();
+    let bitangent = Vector::cross(normal, tangent);
+    (tangent, bitangent)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::EQUALITY_EPSILON;
+
+    use super::*;
+
+    #[test]
+    fn same_seed_same_sequence() {
+        let mut a = Rng::new(42);
+        let mut b = Rng::new(42);
+        for _ in 0..100 {
+            assert_eq!(a.next_u64(), b.next_u64());
+        }
+    }
+
+    #[test]
+    fn different_seed_different_sequence() {
+        let mut a = Rng::new(1);
+        let mut b = Rng::new(2);
+        assert_ne!(a.next_u64(), b.next_u64());
+    }
+
+    #[test]
+    fn floats_in_unit_interval() {
+        let mut rng = Rng::new(7);
+        for _ in 0..1000 {
+            let x = rng.next_f64();
+            assert!((0.0..1.0).contains(&x));
+        }
+    }
+
+    #[test]
+    fn below_stays_in_bounds() {
+        let mut rng = Rng::new(7);
+        for _ in 0..1000 {
+            assert!(rng.below(10) < 10);
+        }
+    }
+
+    #[test]
+    fn seeding_repeats_thread_sequence() {
+        seed(3);
+        let first = (random(), random());
+        seed(3);
+        assert_eq!(first, (random(), random()));
+    }
+
+    #[test]
+    fn unit_sphere_samples_stay_inside() {
+        seed(5);
+        for _ in 0..1000 {
+            assert!(in_unit_sphere().magnitude() < 1.0);
+        }
+    }
+
+    #[test]
+    fn unit_disk_samples_stay_inside() {
+        seed(5);
+        for _ in 0..1000 {
+            let (x, y) = in_unit_disk();
+            assert!(x * x + y * y <= 1.0);
+        }
+    }
+
+    #[test]
+    fn orthonormal_basis_is_perpendicular() {
+        let normal = Vector::new(1.0, 2.0, -3.0).normalize();
+        let (tangent, bitangent) = orthonormal_basis(normal);
+        assert!(Vector::dot(normal, tangent).abs() < EQUALITY_EPSILON);
+        assert!(Vector::dot(normal, bitangent).abs() < EQUALITY_EPSILON);
+        assert!(Vector::dot(tangent, bitangent).abs() < EQUALITY_EPSILON);
+        assert!((tangent.magnitude() - 1.0).abs() < EQUALITY_EPSILON);
+        assert!((bitangent.magnitude() - 1.0).abs() < EQUALITY_EPSILON);
+    }
+
+    #[test]
+    fn hemisphere_samples_face_normal() {
+        seed(11);
+        let normal = Vector::new(0.0, 0.0, -1.0);
+        let mut average_cosine = 0.0;
+        for _ in 0..1000 {
+            let direction = cosine_weighted_hemisphere(normal);
+            let cosine = Vector::dot(direction, normal);
+            assert!(cosine >= 0.0);
+            assert!((direction.magnitude() - 1.0).abs() < EQUALITY_EPSILON);
+            average_cosine += cosine / 1000.0;
+        }
+        // The mean cosine of a cosine-weighted hemisphere is 2/3
+        assert!((average_cosine - 2.0 / 3.0).abs() < 0.03);
+    }
+}
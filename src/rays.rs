@@ -7,11 +7,31 @@ use crate::{
 pub struct Ray {
     pub origin: Point,
     pub direction: Vector,
+    /// In micrometres, for rays carrying a single colour through dispersive
+    /// materials. White light has none.
+    pub wavelength: Option<f64>,
+    /// When the ray was cast, within the camera's shutter interval.
+    pub time: f64,
 }
 
 impl Ray {
     pub fn new(origin: Point, direction: Vector) -> Self {
-        Ray { origin, direction }
+        Ray {
+            origin,
+            direction,
+            wavelength: None,
+            time: 0.0,
+        }
+    }
+
+    pub fn with_time(mut self, time: f64) -> Self {
+        self.time = time;
+        self
+    }
+
+    pub fn with_wavelength(mut self, wavelength: Option<f64>) -> Self {
+        self.wavelength = wavelength;
+        self
     }
 
     pub fn position(&self, t: f64) -> Point {
@@ -22,6 +42,8 @@ impl Ray {
         Ray {
             origin: transform * self.origin,
             direction: transform * self.direction,
+            wavelength: self.wavelength,
+            time: self.time,
         }
     }
 }
@@ -42,6 +64,8 @@ mod test {
         let r = Ray::new(origin, direction);
         assert_eq!(r.origin, origin);
         assert_eq!(r.direction, direction);
+        assert_eq!(r.wavelength, None);
+        assert_eq!(r.time, 0.0);
     }
 
     #[test]
@@ -70,4 +94,14 @@ mod test {
         assert_eq!(r2.origin, Point::new(2.0, 6.0, 12.0));
         assert_eq!(r2.direction, Vector::new(0.0, 3.0, 0.0));
     }
+
+    #[test]
+    fn transforming_keeps_wavelength_and_time() {
+        let r = Ray::new(Point::new(1.0, 2.0, 3.0), Vector::new(0.0, 1.0, 0.0))
+            .with_wavelength(Some(0.55))
+            .with_time(0.25);
+        let r2 = r.transformed(&translation(3.0, 4.0, 5.0));
+        assert_eq!(r2.wavelength, Some(0.55));
+        assert_eq!(r2.time, 0.25);
+    }
 }
@@ -1,12 +1,12 @@
 use crate::{
     intersections::{Intersection, Intersections},
     materials::Material,
-    matrices::{Matrix, Transform, IDENTITY},
+    matrices::{Decomposition, Matrix, Transform, IDENTITY},
     rays::Ray,
     Point, Vector,
 };
 
-use std::{any::Any, fmt::Debug};
+use std::{any::Any, borrow::Cow, fmt::Debug};
 
 mod planes;
 mod spheres;
@@ -62,6 +62,7 @@ pub trait ShapeModel: Clone + Debug + PartialEq + 'static {
 pub struct Shape {
     transform: Transform,
     inverse: Transform,
+    motion: Option<Motion>,
     pub material: Material,
     pub model: Box<dyn DynamicShapeModel>,
 }
@@ -69,11 +70,28 @@ pub struct Shape {
 #[derive(Default, Debug, Copy, Clone, PartialEq, Eq)]
 pub struct NoInverseError;
 
+/// Where a moving shape ends up, with both ends decomposed up front so
+/// blending them doesn't have to.
+#[derive(Debug, Clone, PartialEq)]
+struct Motion {
+    end: Transform,
+    // None if either end can't be decomposed, so blends fall back to lerp
+    parts: Option<(Decomposition, Decomposition)>,
+}
+
+impl Motion {
+    fn new(start: &Transform, end: Transform) -> Self {
+        let parts = start.decompose().zip(end.decompose());
+        Motion { end, parts }
+    }
+}
+
 impl Shape {
     pub fn new(model: impl ShapeModel) -> Self {
         Shape {
             transform: IDENTITY,
             inverse: IDENTITY,
+            motion: None,
             material: Material::default(),
             model: Box::new(model),
         }
@@ -81,6 +99,9 @@ impl Shape {
 
     pub fn set_transform(&mut self, transform: Transform) -> Result<(), NoInverseError> {
         let inverse = transform.inverse().ok_or(NoInverseError)?;
+        if let Some(motion) = self.motion.take() {
+            self.motion = Some(Motion::new(&transform, motion.end));
+        }
         self.transform = transform;
         self.inverse = inverse;
         Ok(())
@@ -94,8 +115,46 @@ impl Shape {
         &self.inverse
     }
 
-    pub fn intersect(&self, ray: &Ray) -> Intersections {
-        let local_ray = ray.transformed(&self.inverse);
+    /// Makes the shape move from its transform at time 0 to `end` at time 1,
+    /// so it blurs across the camera's shutter interval.
+    pub fn set_motion(&mut self, end: Transform) -> Result<(), NoInverseError> {
+        if !end.invertible() {
+            return Err(NoInverseError);
+        }
+        self.motion = Some(Motion::new(&self.transform, end));
+        Ok(())
+    }
+
+    pub fn get_end_transform(&self) -> Option<&Transform> {
+        self.motion.as_ref().map(|motion| &motion.end)
+    }
+
+    pub fn transform_at(&self, time: f64) -> Cow<'_, Transform> {
+        let Some(motion) = &self.motion else {
+            return Cow::Borrowed(&self.transform);
+        };
+        let time = time.clamp(0.0, 1.0);
+        Cow::Owned(match &motion.parts {
+            Some((start, end)) => start.interpolate(end, time).transform(),
+            None => self.transform.lerp(&motion.end, time),
+        })
+    }
+
+    pub fn inverse_at(&self, time: f64) -> Cow<'_, Transform> {
+        let Some(motion) = &self.motion else {
+            return Cow::Borrowed(&self.inverse);
+        };
+        let time = time.clamp(0.0, 1.0);
+        let inverse = match &motion.parts {
+            Some((start, end)) => start.interpolate(end, time).inverse(),
+            None => self.transform.lerp(&motion.end, time).inverse(),
+        };
+        // Falls back to the start if the blend happens to be singular
+        inverse.map_or(Cow::Borrowed(&self.inverse), Cow::Owned)
+    }
+
+    pub fn intersect(&self, ray: &Ray) -> Intersections<'_> {
+        let local_ray = ray.transformed(&self.inverse_at(ray.time));
         Intersections::new(
             self.model
                 .local_intersect(&local_ray)
@@ -106,12 +165,17 @@ impl Shape {
     }
 
     pub fn normal_at(&self, point: Point) -> Vector {
-        let local_point = &self.inverse * point;
+        self.normal_at_time(point, 0.0)
+    }
+
+    /// The normal at `point`, where the shape is at `time`.
+    pub fn normal_at_time(&self, point: Point, time: f64) -> Vector {
+        let inverse = self.inverse_at(time);
+        let local_point = inverse.as_ref() * point;
         let local_normal = self.model.local_normal_at(local_point);
         let local_normal_matrix =
             Matrix::new([[local_normal.x], [local_normal.y], [local_normal.z]]);
-        let world_normal_matrix = &self
-            .inverse
+        let world_normal_matrix = &inverse
             .submatrix(3, 3)
             .expect("matrix index error")
             .transpose()
@@ -130,6 +194,7 @@ impl Clone for Shape {
         Shape {
             transform: self.transform.clone(),
             inverse: self.inverse.clone(),
+            motion: self.motion.clone(),
             material: self.material.clone(),
             model: self.model.dynamic_clone(),
         }
@@ -140,6 +205,7 @@ impl PartialEq for Shape {
     fn eq(&self, other: &Self) -> bool {
         self.transform == other.transform
             && self.inverse == other.inverse
+            && self.motion == other.motion
             && self.material == other.material
             && self.model.dynamic_eq(other.model.as_ref())
     }
@@ -151,6 +217,7 @@ mod test {
 
     use crate::{
         matrices::IDENTITY,
+        shapes::Sphere,
         transformations::{rotation_z, scaling, translation},
         Point, Vector,
     };
@@ -252,4 +319,57 @@ mod test {
         let n = s.normal_at(Point::new(0.0, 2_f64.sqrt() / 2.0, -(2_f64.sqrt()) / 2.0));
         assert_eq!(n, Vector::new(0.0, 0.97014, -0.24254));
     }
+
+    #[test]
+    fn moving_shape_is_hit_where_it_is_at_ray_time() {
+        let mut s = Shape::new(Sphere);
+        s.set_motion(translation(0.0, 4.0, 0.0)).unwrap();
+        let r = Ray::new(Point::new(0.0, 2.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+        assert!(s.intersect(&r).is_empty());
+        let xs = s.intersect(&r.clone().with_time(0.5));
+        assert_eq!(xs.len(), 2);
+        assert_eq!(xs[0].t, 4.0);
+        assert!(s.intersect(&r.with_time(1.0)).is_empty());
+    }
+
+    #[test]
+    fn moving_shape_normal_follows_time() {
+        let mut s = Shape::new(Sphere);
+        s.set_motion(translation(0.0, 4.0, 0.0)).unwrap();
+        assert_eq!(
+            s.normal_at_time(Point::new(0.0, 3.0, 0.0), 1.0),
+            Vector::new(0.0, -1.0, 0.0)
+        );
+        assert_eq!(s.transform_at(0.5).as_ref(), &translation(0.0, 2.0, 0.0));
+    }
+
+    #[test]
+    fn moving_shape_inverse_matches_its_transform() {
+        let mut s = Shape::new(Sphere);
+        s.set_transform(scaling(1.0, 2.0, 1.0)).unwrap();
+        s.set_motion(&translation(3.0, 0.0, 0.0) * &rotation_z(PI / 2.0))
+            .unwrap();
+        for time in [0.0, 0.3, 1.0] {
+            assert_eq!(
+                s.inverse_at(time).as_ref(),
+                &s.transform_at(time).inverse().unwrap()
+            );
+        }
+    }
+
+    #[test]
+    fn changing_start_keeps_motion_end() {
+        let mut s = Shape::new(Sphere);
+        s.set_motion(translation(0.0, 4.0, 0.0)).unwrap();
+        s.set_transform(translation(0.0, 2.0, 0.0)).unwrap();
+        assert_eq!(s.get_end_transform(), Some(&translation(0.0, 4.0, 0.0)));
+        assert_eq!(s.transform_at(0.5).as_ref(), &translation(0.0, 3.0, 0.0));
+    }
+
+    #[test]
+    fn motion_needs_invertible_end() {
+        let mut s = Shape::new(Sphere);
+        assert_eq!(s.set_motion(scaling(0.0, 1.0, 1.0)), Err(NoInverseError));
+        assert_eq!(s.get_end_transform(), None);
+    }
 }
@@ -1,5 +1,6 @@
 use crate::{
     matrices::{Transform, IDENTITY},
+    quaternions::Quaternion,
     Point, Vector,
 };
 
@@ -53,6 +54,11 @@ pub fn rotation_z(r: f64) -> Transform {
     .expect("casting transform")
 }
 
+/// Rotates by `r` radians around `axis`, which runs through the origin.
+pub fn rotation(axis: Vector, r: f64) -> Transform {
+    Quaternion::from_axis_angle(axis, r).to_transform()
+}
+
 pub fn shearing(
     x_by_y: f64,
     x_by_z: f64,
@@ -127,6 +133,12 @@ impl Builder {
         }
     }
 
+    pub fn rotation(self, axis: Vector, r: f64) -> Builder {
+        Builder {
+            current: &rotation(axis, r) * &self.current,
+        }
+    }
+
     pub fn shearing(
         self,
         x_by_y: f64,
@@ -251,6 +263,18 @@ mod test {
         assert_eq!(&full_quarter * p, Point::new(-1.0, 0.0, 0.0));
     }
 
+    #[test]
+    fn rotate_around_axis() {
+        let diagonal = Vector::new(1.0, 1.0, 1.0);
+        let third = rotation(diagonal, 2.0 * PI / 3.0);
+        assert_eq!(
+            &third * Point::new(1.0, 0.0, 0.0),
+            Point::new(0.0, 1.0, 0.0)
+        );
+        assert_eq!(&third * diagonal, diagonal);
+        assert_eq!(rotation(Vector::new(0.0, 2.0, 0.0), 0.7), rotation_y(0.7));
+    }
+
     #[test]
     fn shear_x_by_y() {
         let transform = shearing(1.0, 0.0, 0.0, 0.0, 0.0, 0.0);
@@ -1,19 +1,98 @@
 use crate::{
-    canvas::Color,
+    backgrounds::Background,
+    canvas::{Color, WHITE},
     intersections::{HitInfo, Intersection, Intersections},
     lights::PointLight,
-    materials::lighting,
+    materials::{
+        lighting, microfacet, sample_microfacet, Material, Shading, SurfacePoint, Visibility,
+    },
+    media::{Medium, Volume},
+    random::{cosine_weighted_hemisphere, in_unit_sphere, random},
     rays::Ray,
     shapes::Shape,
     Point, Vector,
 };
 
 pub const RECURSION_DEPTH: usize = 5;
+pub const PATH_DEPTH: usize = 16;
+/// Below one step of an 8-bit channel, further bounces can't change a pixel.
+pub const CONTRIBUTION_CUTOFF: f64 = 1.0 / 255.0;
+
+/// How much further a ray may go on spawning reflected and refracted rays.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RayBudget {
+    /// Bounces left.
+    pub depth: usize,
+    /// How much the ray's colour still counts towards the pixel.
+    pub weight: f64,
+    /// Rays weighing less than this aren't traced.
+    pub cutoff: f64,
+    /// Whether a glossy surface may still split the ray into
+    /// `glossy_samples` rays. Only the first glossy hit does, so the ray count
+    /// doesn't multiply with every bounce.
+    pub fan_out: bool,
+}
+
+impl RayBudget {
+    pub fn new(depth: usize, cutoff: f64) -> Self {
+        RayBudget {
+            depth,
+            weight: 1.0,
+            cutoff,
+            fan_out: true,
+        }
+    }
+
+    /// The budget for a secondary ray whose colour is scaled by `weight`, or
+    /// `None` if that ray shouldn't be traced at all.
+    pub fn bounce(&self, weight: f64) -> Option<Self> {
+        let weight = self.weight * weight;
+        if self.depth == 0 || weight < self.cutoff {
+            return None;
+        }
+
+        Some(RayBudget {
+            depth: self.depth - 1,
+            weight,
+            ..*self
+        })
+    }
+}
+
+/// Darkens the ambient term where nearby geometry hides most of the sky.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AmbientOcclusion {
+    /// Rays cast from each shaded point.
+    pub samples: usize,
+    /// How far away geometry can be and still occlude.
+    pub distance: f64,
+}
+
+impl AmbientOcclusion {
+    pub fn new(samples: usize, distance: f64) -> Self {
+        AmbientOcclusion { samples, distance }
+    }
+}
+
+// Wavelengths in micrometres standing in for the red, green and blue channels
+// when dispersion splits white light apart
+const CHANNEL_WAVELENGTHS: [f64; 3] = [0.65, 0.55, 0.45];
+
+// Paths always get this many bounces before Russian roulette may end them
+const ROULETTE_START: usize = 3;
 
 #[derive(Default, Debug, Clone, PartialEq)]
 pub struct World {
     pub objects: Vec<Shape>,
     pub light: PointLight,
+    pub background: Background,
+    /// How many rays `shade_hit` casts to gather light from emissive objects.
+    /// With zero, emissive objects glow but don't light anything else.
+    pub emissive_samples: usize,
+    /// Fills all of space, including behind the background.
+    pub fog: Option<Medium>,
+    pub volumes: Vec<Volume>,
+    pub ambient_occlusion: Option<AmbientOcclusion>,
 }
 
 impl World {
@@ -21,7 +100,7 @@ impl World {
         World::default()
     }
 
-    pub fn intersect(&self, ray: &Ray) -> Intersections {
+    pub fn intersect(&self, ray: &Ray) -> Intersections<'_> {
         let vec = self
             .objects
             .iter()
@@ -30,22 +109,30 @@ impl World {
         Intersections::new(vec)
     }
 
-    pub fn shade_hit(&self, hit_info: &HitInfo, remaining: usize) -> Color {
-        let is_shadowed = self.is_shadowed(hit_info.over_point);
+    pub fn shade_hit(&self, hit_info: &HitInfo, budget: RayBudget) -> Color {
+        let visibility = Visibility {
+            in_shadow: self.is_shadowed_at(hit_info.over_point, hit_info.time),
+            ambient: self.ambient_visibility(hit_info),
+        };
         let surface = lighting(
             &hit_info.object.material,
             hit_info.object,
             &self.light,
-            hit_info.point,
+            SurfacePoint {
+                point: hit_info.point,
+                time: hit_info.time,
+            },
             hit_info.eyev,
             hit_info.normal,
-            is_shadowed,
+            visibility,
         );
 
-        let reflected = self.reflected_color(hit_info, remaining);
-        let refracted = self.refracted_color(hit_info, remaining);
-
         let material = &hit_info.object.material;
+        let surface = surface + material.emissive + self.emissive_light(hit_info);
+
+        let reflected = self.reflected_color(hit_info, budget);
+        let refracted = self.refracted_color(hit_info, budget);
+
         if material.reflective > 0.0 && material.transparaency > 0.0 {
             let reflectance = Self::schlick(hit_info);
             surface + reflected * reflectance + refracted * (1.0 - reflectance)
@@ -54,57 +141,388 @@ impl World {
         }
     }
 
-    pub fn color_from(&self, ray: &Ray, remaining: usize) -> Color {
+    pub fn color_from(&self, ray: &Ray, budget: RayBudget) -> Color {
         let intersections = self.intersect(ray);
         let Some(hit_index) = intersections.hit() else {
-            return Color::default();
+            let background = self.background.color_for(ray.direction);
+            return self.through_media(ray, f64::INFINITY, background);
         };
+        if ray.wavelength.is_none() && intersections[hit_index].object.material.is_dispersive() {
+            return self.dispersed_color(ray, budget);
+        }
         let hit_info = HitInfo::prepare(&intersections, ray, hit_index).expect("invalid hit index");
-        self.shade_hit(&hit_info, remaining)
+        let color = self.shade_hit(&hit_info, budget) * Self::transmittance(&hit_info, ray);
+        self.through_media(ray, hit_info.t, color)
+    }
+
+    /// Dims `color`, seen at `end` along `ray`, by the fog and volumes in
+    /// between, and adds the light they scatter towards the ray's origin.
+    fn through_media(&self, ray: &Ray, end: f64, color: Color) -> Color {
+        let segments = self.media_segments(ray, end);
+        if segments.is_empty() {
+            return color;
+        }
+
+        let mut scattered = Color::new(0.0, 0.0, 0.0);
+        for (start, stop, medium) in &segments {
+            let step = (stop - start) / medium.steps as f64;
+            for index in 0..medium.steps {
+                let t = start + (index as f64 + 0.5) * step;
+                let point = ray.position(t);
+                if self.is_shadowed_at(point, ray.time) {
+                    continue;
+                }
+                let weight = (-Self::optical_depth(&segments, ray, t)).exp()
+                    * medium.density
+                    * step
+                    * ray.direction.magnitude();
+                let light = self.light.intensity * self.light_transmittance(point, ray.time);
+                scattered = scattered + light * medium.color * weight;
+            }
+        }
+
+        color * (-Self::optical_depth(&segments, ray, end)).exp() + scattered
+    }
+
+    /// The stretches of `ray`, up to `end`, that pass through fog or volumes.
+    fn media_segments(&self, ray: &Ray, end: f64) -> Vec<(f64, f64, &Medium)> {
+        let mut segments = Vec::new();
+        if let Some(fog) = self.fog.as_ref().filter(|fog| fog.density > 0.0) {
+            let horizon = fog.horizon() / ray.direction.magnitude();
+            segments.push((0.0, end.min(horizon), fog));
+        }
+        for volume in &self.volumes {
+            for (start, stop) in volume.segments(ray) {
+                if stop > 0.0 && start < end {
+                    segments.push((start.max(0.0), stop.min(end), &volume.medium));
+                }
+            }
+        }
+        segments
+    }
+
+    /// How much the media in `segments` thin out light along `ray` up to `t`.
+    fn optical_depth(segments: &[(f64, f64, &Medium)], ray: &Ray, t: f64) -> f64 {
+        segments
+            .iter()
+            .map(|(start, stop, medium)| medium.density * (t.min(*stop) - start).max(0.0))
+            .sum::<f64>()
+            * ray.direction.magnitude()
+    }
+
+    /// The fraction of the light that makes it through fog and volumes to
+    /// `point`.
+    fn light_transmittance(&self, point: Point, time: f64) -> f64 {
+        let ray = Ray::new(point, self.light.position - point).with_time(time);
+        let segments = self.media_segments(&ray, 1.0);
+        (-Self::optical_depth(&segments, &ray, 1.0)).exp()
+    }
+
+    /// Traces `ray` once per colour channel, each at its own wavelength, so
+    /// dispersive materials bend the channels by different amounts.
+    fn dispersed_color(&self, ray: &Ray, budget: RayBudget) -> Color {
+        let [red, green, blue] = CHANNEL_WAVELENGTHS.map(|wavelength| {
+            self.color_from(&ray.clone().with_wavelength(Some(wavelength)), budget)
+        });
+        Color::new(red.red(), green.green(), blue.blue())
+    }
+
+    /// How much light survives the trip along `ray` through whatever object it
+    /// was inside of before reaching the hit.
+    fn transmittance(hit_info: &HitInfo, ray: &Ray) -> Color {
+        match hit_info.medium {
+            Some(medium) => medium
+                .material
+                .transmittance(hit_info.t * ray.direction.magnitude()),
+            None => WHITE,
+        }
     }
 
     pub fn is_shadowed(&self, point: Point) -> bool {
+        self.is_shadowed_at(point, 0.0)
+    }
+
+    /// Like `is_shadowed`, with moving objects where they are at `time`.
+    pub fn is_shadowed_at(&self, point: Point, time: f64) -> bool {
         let light_to_point = self.light.position - point;
         let distance = light_to_point.magnitude();
         let direction = light_to_point.normalize();
 
-        let ray = Ray::new(point, direction);
+        let ray = Ray::new(point, direction).with_time(time);
         let intersections = self.intersect(&ray);
         if let Some(hit_index) = intersections.hit() {
-            intersections[hit_index].t < distance
+            intersections[hit_index..].iter().any(|intersection| {
+                intersection.t < distance && intersection.object.material.casts_shadow
+            })
         } else {
             false
         }
     }
 
-    pub fn reflected_color(&self, hit_info: &HitInfo, remaining: usize) -> Color {
-        if remaining == 0 || hit_info.object.material.reflective == 0.0 {
+    /// The fraction of `ambient_occlusion` rays from the hit that escape
+    /// without hitting anything nearby, or 1 when it's turned off.
+    pub fn ambient_visibility(&self, hit_info: &HitInfo) -> f64 {
+        let Some(occlusion) = self
+            .ambient_occlusion
+            .filter(|occlusion| occlusion.samples > 0)
+        else {
+            return 1.0;
+        };
+
+        let mut escaped = 0;
+        for _ in 0..occlusion.samples {
+            let direction = cosine_weighted_hemisphere(hit_info.normal);
+            let intersections = self.intersect(&hit_info.spawn_ray(hit_info.over_point, direction));
+            let blocked = intersections
+                .hit()
+                .is_some_and(|hit_index| intersections[hit_index].t < occlusion.distance);
+            if !blocked {
+                escaped += 1;
+            }
+        }
+        escaped as f64 / occlusion.samples as f64
+    }
+
+    /// Diffuse light arriving from emissive objects, estimated with
+    /// `emissive_samples` random rays. Casts none if nothing in the world
+    /// glows.
+    pub fn emissive_light(&self, hit_info: &HitInfo) -> Color {
+        let glowing = self
+            .objects
+            .iter()
+            .any(|object| object.material.is_emissive());
+        if self.emissive_samples == 0 || !glowing {
             return Color::new(0.0, 0.0, 0.0);
         }
 
-        let reflect_ray = Ray::new(hit_info.over_point, hit_info.reflectv);
-        let color = self.color_from(&reflect_ray, remaining - 1);
+        let mut total = Color::new(0.0, 0.0, 0.0);
+        for _ in 0..self.emissive_samples {
+            let direction = cosine_weighted_hemisphere(hit_info.normal);
+            let ray = hit_info.spawn_ray(hit_info.over_point, direction);
+            let intersections = self.intersect(&ray);
+            if let Some(hit_index) = intersections.hit() {
+                total = total + intersections[hit_index].object.material.emissive;
+            }
+        }
 
-        color * hit_info.object.material.reflective
+        let material = &hit_info.object.material;
+        let albedo =
+            material.color_at(hit_info.object, hit_info.point, hit_info.time) * material.diffuse;
+        albedo * total * (1.0 / self.emissive_samples as f64)
     }
 
-    pub fn refracted_color(&self, hit_info: &HitInfo, remaining: usize) -> Color {
-        if remaining == 0 || hit_info.object.material.transparaency == 0.0 {
+    pub fn reflected_color(&self, hit_info: &HitInfo, budget: RayBudget) -> Color {
+        let material = &hit_info.object.material;
+        if material.reflective == 0.0 {
             return Color::new(0.0, 0.0, 0.0);
         }
+        let Some(budget) = budget.bounce(material.reflective) else {
+            return Color::new(0.0, 0.0, 0.0);
+        };
+
+        let color = Self::sample_lobe(
+            material,
+            hit_info.reflectv,
+            hit_info.normal,
+            budget,
+            |direction, budget| {
+                self.color_from(&hit_info.spawn_ray(hit_info.over_point, direction), budget)
+            },
+        );
+
+        color * material.reflective
+    }
+
+    pub fn refracted_color(&self, hit_info: &HitInfo, budget: RayBudget) -> Color {
+        let material = &hit_info.object.material;
+        if material.transparaency == 0.0 {
+            return Color::new(0.0, 0.0, 0.0);
+        }
+        let Some(budget) = budget.bounce(material.transparaency) else {
+            return Color::new(0.0, 0.0, 0.0);
+        };
+
+        let Some(direction) = Self::refraction_direction(hit_info) else {
+            return Color::new(0.0, 0.0, 0.0);
+        };
+        let color = Self::sample_lobe(
+            material,
+            direction,
+            hit_info.normal,
+            budget,
+            |direction, budget| {
+                self.color_from(&hit_info.spawn_ray(hit_info.under_point, direction), budget)
+            },
+        );
+
+        color * material.transparaency
+    }
+
+    /// Averages `trace` over `glossy_samples` directions scattered around
+    /// `direction` by the material's glossiness, or traces a single scattered
+    /// direction if the budget has already fanned out.
+    fn sample_lobe(
+        material: &Material,
+        direction: Vector,
+        normal: Vector,
+        budget: RayBudget,
+        trace: impl Fn(Vector, RayBudget) -> Color,
+    ) -> Color {
+        if material.glossiness == 0.0 || material.glossy_samples == 0 {
+            return trace(direction, budget);
+        }
+
+        let samples = if budget.fan_out {
+            material.glossy_samples
+        } else {
+            1
+        };
+        let budget = RayBudget {
+            fan_out: false,
+            ..budget
+        };
+        let mut total = Color::new(0.0, 0.0, 0.0);
+        for _ in 0..samples {
+            total = total
+                + trace(
+                    Self::roughen(direction, normal, material.glossiness),
+                    budget,
+                );
+        }
+        total * (1.0 / samples as f64)
+    }
+
+    /// Nudges `direction` randomly by up to `glossiness`, trying again until
+    /// it stays on the same side of the surface.
+    fn roughen(direction: Vector, normal: Vector, glossiness: f64) -> Vector {
+        let side = Vector::dot(direction, normal);
+        if glossiness == 0.0 || side == 0.0 {
+            return direction;
+        }
 
+        loop {
+            let jittered = (direction.normalize() + in_unit_sphere() * glossiness).normalize();
+            if Vector::dot(jittered, normal) * side > 0.0 {
+                return jittered;
+            }
+        }
+    }
+
+    /// Returns `None` on total internal reflection.
+    pub fn refraction_direction(hit_info: &HitInfo) -> Option<Vector> {
         let n_ratio = hit_info.n1 / hit_info.n2;
         let cos_i = Vector::dot(hit_info.eyev, hit_info.normal);
         let sin2_t = n_ratio * n_ratio * (1.0 - cos_i * cos_i);
 
         if sin2_t > 1.0 {
-            return Color::new(0.0, 0.0, 0.0);
+            return None;
         }
 
         let cos_t = (1.0 - sin2_t).sqrt();
-        let direction = hit_info.normal * (n_ratio * cos_i - cos_t) - hit_info.eyev * n_ratio;
-        let refract_ray = Ray::new(hit_info.under_point, direction);
-        self.color_from(&refract_ray, remaining - 1) * hit_info.object.material.transparaency
+        Some(hit_info.normal * (n_ratio * cos_i - cos_t) - hit_info.eyev * n_ratio)
+    }
+
+    /// Estimates the light arriving along `ray` by following a single random
+    /// path through the scene, bouncing at most `max_depth` times.
+    ///
+    /// Average many of these per pixel to converge on a picture with indirect
+    /// lighting. At each surface the path picks one of reflection, refraction or
+    /// a diffuse bounce, with probabilities from the material's `reflective`
+    /// and `transparaency`. Emissive surfaces and the background light the
+    /// scene, and `light` is sampled directly at every diffuse bounce. Fog and
+    /// volumes aren't simulated.
+    pub fn path_traced_color(&self, ray: &Ray, max_depth: usize) -> Color {
+        let mut radiance = Color::new(0.0, 0.0, 0.0);
+        let mut throughput = WHITE;
+        let mut ray = ray.clone();
+
+        for bounce in 0..max_depth {
+            let intersections = self.intersect(&ray);
+            let Some(hit_index) = intersections.hit() else {
+                radiance = radiance + throughput * self.background.color_for(ray.direction);
+                break;
+            };
+            if ray.wavelength.is_none() && intersections[hit_index].object.material.is_dispersive()
+            {
+                // Follow one channel from here on, weighted to make up for the
+                // other two
+                let channel = ((random() * 3.0) as usize).min(2);
+                ray.wavelength = Some(CHANNEL_WAVELENGTHS[channel]);
+                let mut mask = [0.0; 3];
+                mask[channel] = 3.0;
+                throughput = throughput * Color::new(mask[0], mask[1], mask[2]);
+            }
+            let hit_info =
+                HitInfo::prepare(&intersections, &ray, hit_index).expect("invalid hit index");
+            let material = &hit_info.object.material;
+            throughput = throughput * Self::transmittance(&hit_info, &ray);
+            radiance = radiance + throughput * material.emissive;
+
+            if bounce >= ROULETTE_START {
+                let survival = throughput
+                    .red()
+                    .max(throughput.green())
+                    .max(throughput.blue())
+                    .clamp(0.05, 1.0);
+                if random() >= survival {
+                    break;
+                }
+                throughput = throughput * (1.0 / survival);
+            }
+
+            let choice = random();
+            let reflection = || {
+                hit_info.spawn_ray(
+                    hit_info.over_point,
+                    Self::roughen(hit_info.reflectv, hit_info.normal, material.glossiness),
+                )
+            };
+            if choice < material.reflective {
+                ray = reflection();
+            } else if choice < material.reflective + material.transparaency {
+                ray = match Self::refraction_direction(&hit_info) {
+                    Some(direction) if random() >= Self::schlick(&hit_info) => hit_info.spawn_ray(
+                        hit_info.under_point,
+                        Self::roughen(direction, hit_info.normal, material.glossiness),
+                    ),
+                    _ => reflection(),
+                };
+            } else if material.shading == Shading::Microfacet {
+                let color = material.color_at(hit_info.object, hit_info.point, hit_info.time);
+                let lightv = (self.light.position - hit_info.point).normalize();
+                let (diffuse, specular) =
+                    microfacet(material, color, lightv, hit_info.eyev, hit_info.normal);
+                radiance =
+                    radiance + throughput * (diffuse + specular) * self.direct_light(&hit_info);
+                let Some((direction, weight)) =
+                    sample_microfacet(material, color, hit_info.eyev, hit_info.normal)
+                else {
+                    break;
+                };
+                throughput = throughput * weight;
+                ray = hit_info.spawn_ray(hit_info.over_point, direction);
+            } else {
+                let albedo = material.color_at(hit_info.object, hit_info.point, hit_info.time)
+                    * material.diffuse;
+                throughput = throughput * albedo;
+                radiance = radiance + throughput * self.direct_light(&hit_info);
+                ray = hit_info.spawn_ray(
+                    hit_info.over_point,
+                    cosine_weighted_hemisphere(hit_info.normal),
+                );
+            }
+        }
+
+        radiance
+    }
+
+    /// Light from `light` reaching a diffuse surface, unless it's in shadow.
+    fn direct_light(&self, hit_info: &HitInfo) -> Color {
+        if self.is_shadowed_at(hit_info.over_point, hit_info.time) {
+            return Color::new(0.0, 0.0, 0.0);
+        }
+        let lightv = (self.light.position - hit_info.point).normalize();
+        self.light.intensity * Vector::dot(lightv, hit_info.normal).max(0.0)
     }
 
     pub fn schlick(hit_info: &HitInfo) -> f64 {
@@ -141,16 +559,20 @@ pub(crate) fn default_world() -> World {
     World {
         objects: vec![s1, s2],
         light,
+        ..World::default()
     }
 }
 
 #[cfg(test)]
 mod test {
+    use std::{cell::Cell, f64::consts::PI, rc::Rc};
+
     use crate::{
-        canvas::Color,
+        canvas::{Color, BLACK},
         patterns::{Pattern, TestPattern},
+        random,
         rays::Ray,
-        shapes::{Plane, Sphere},
+        shapes::{Plane, ShapeModel, Sphere},
         transformations::{translation, Builder},
         Point, Vector, EQUALITY_EPSILON,
     };
@@ -201,7 +623,7 @@ mod test {
         let i = Intersection::new(4.0, shape);
         let xs = Intersections::new(vec![i.clone()]);
         let hit_info = HitInfo::prepare(&xs, &r, 0).unwrap();
-        let c = w.shade_hit(&hit_info, RECURSION_DEPTH);
+        let c = w.shade_hit(&hit_info, RayBudget::new(RECURSION_DEPTH, 0.0));
         assert_eq!(c, Color::new(0.38066, 0.47583, 0.2855));
     }
 
@@ -214,7 +636,7 @@ mod test {
         let i = Intersection::new(0.5, shape);
         let xs = Intersections::new(vec![i.clone()]);
         let hit_info = HitInfo::prepare(&xs, &r, 0).unwrap();
-        let c = w.shade_hit(&hit_info, RECURSION_DEPTH);
+        let c = w.shade_hit(&hit_info, RayBudget::new(RECURSION_DEPTH, 0.0));
         assert_eq!(c, Color::new(0.90498, 0.90498, 0.90498));
     }
 
@@ -222,15 +644,219 @@ mod test {
     fn color_from_miss() {
         let w = default_world();
         let r = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 1.0, 0.0));
-        let c = w.color_from(&r, RECURSION_DEPTH);
+        let c = w.color_from(&r, RayBudget::new(RECURSION_DEPTH, 0.0));
+        assert_eq!(c, Color::new(0.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn color_from_miss_with_background() {
+        let mut w = default_world();
+        w.background = Background::Gradient {
+            bottom: Color::new(0.0, 0.0, 0.0),
+            top: Color::new(0.2, 0.4, 1.0),
+        };
+        let r = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 1.0, 0.0));
+        let c = w.color_from(&r, RayBudget::new(RECURSION_DEPTH, 0.0));
+        assert_eq!(c, Color::new(0.2, 0.4, 1.0));
+    }
+
+    #[test]
+    fn reflection_of_background() {
+        let mut w = World::new();
+        w.background = Background::Solid(Color::new(0.5, 0.5, 1.0));
+        let mut floor = Shape::new(Plane);
+        floor.material.ambient = 0.0;
+        floor.material.diffuse = 0.0;
+        floor.material.specular = 0.0;
+        floor.material.reflective = 0.5;
+        w.objects.push(floor);
+        let r = Ray::new(
+            Point::new(0.0, 1.0, -3.0),
+            Vector::new(0.0, -(2_f64.sqrt()) / 2.0, 2_f64.sqrt() / 2.0),
+        );
+        let c = w.color_from(&r, RayBudget::new(RECURSION_DEPTH, 0.0));
+        assert_eq!(c, Color::new(0.25, 0.25, 0.5));
+    }
+
+    #[test]
+    fn path_traced_miss_sees_background() {
+        let mut w = default_world();
+        w.background = Background::Solid(Color::new(0.3, 0.6, 0.9));
+        let r = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 1.0, 0.0));
+        let c = w.path_traced_color(&r, PATH_DEPTH);
+        assert_eq!(c, Color::new(0.3, 0.6, 0.9));
+    }
+
+    #[test]
+    fn path_traced_emissive_surface() {
+        let mut w = World::new();
+        let mut s = Shape::new(Sphere);
+        s.material.diffuse = 0.0;
+        s.material.emissive = Color::new(1.0, 0.5, 0.0);
+        w.objects.push(s);
+        let r = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+        let c = w.path_traced_color(&r, PATH_DEPTH);
+        assert_eq!(c, Color::new(1.0, 0.5, 0.0));
+    }
+
+    #[test]
+    fn path_traced_diffuse_lit_by_sky() {
+        let mut w = World::new();
+        w.background = Background::Solid(Color::new(1.0, 1.0, 1.0));
+        let mut floor = Shape::new(Plane);
+        floor.material.diffuse = 0.5;
+        w.objects.push(floor);
+        let r = Ray::new(
+            Point::new(0.0, 1.0, -1.0),
+            Vector::new(0.0, -1.0, 1.0).normalize(),
+        );
+        for _ in 0..10 {
+            let c = w.path_traced_color(&r, PATH_DEPTH);
+            assert_eq!(c, Color::new(0.5, 0.5, 0.5));
+        }
+    }
+
+    #[test]
+    fn path_traced_microfacet_metal_reflects_sharply() {
+        let mut w = World::new();
+        w.light.intensity = Color::new(0.0, 0.0, 0.0);
+        w.background = Background::Gradient {
+            bottom: Color::new(0.0, 0.0, 0.0),
+            top: Color::new(1.0, 1.0, 1.0),
+        };
+        let mut floor = Shape::new(Plane);
+        floor.material.shading = Shading::Microfacet;
+        floor.material.metallic = 1.0;
+        w.objects.push(floor);
+        // Glancing, so the mirror image is near the horizon while a diffuse
+        // surface would see mostly sky
+        let r = Ray::new(
+            Point::new(0.0, 1.0, -5.0),
+            Vector::new(0.0, -1.0, 5.0).normalize(),
+        );
+        let mirrored = w.background.color_for(Vector::new(0.0, 1.0, 5.0));
+        for _ in 0..10 {
+            let c = w.path_traced_color(&r, PATH_DEPTH);
+            assert!((c.red() - mirrored.red()).abs() < 0.02);
+        }
+    }
+
+    #[test]
+    fn path_traced_no_depth_is_black() {
+        let w = default_world();
+        let r = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+        let c = w.path_traced_color(&r, 0);
         assert_eq!(c, Color::new(0.0, 0.0, 0.0));
     }
 
+    #[test]
+    fn path_traced_shadow_gets_indirect_light() {
+        let mut w = World::new();
+        w.light = PointLight::new(Point::new(0.0, 10.0, 0.0), Color::new(1.0, 1.0, 1.0));
+        let floor = Shape::new(Plane);
+        let mut blocker = Shape::new(Sphere);
+        blocker.set_transform(translation(0.0, 5.0, 0.0)).unwrap();
+        w.objects = vec![floor, blocker];
+        let r = Ray::new(
+            Point::new(0.0, 1.0, -1.0),
+            Vector::new(0.0, -1.0, 1.0).normalize(),
+        );
+        assert!(w.is_shadowed(Point::new(0.0, 0.0001, 0.0)));
+        assert_eq!(
+            w.color_from(&r, RayBudget::new(RECURSION_DEPTH, 0.0)),
+            Color::new(0.1, 0.1, 0.1)
+        );
+
+        crate::random::seed(5);
+        let samples = 200;
+        let mut total = Color::new(0.0, 0.0, 0.0);
+        for _ in 0..samples {
+            total = total + w.path_traced_color(&r, PATH_DEPTH);
+        }
+        assert!(total.red() / samples as f64 > 0.01);
+    }
+
+    #[test]
+    fn shade_hit_includes_emission() {
+        let mut w = default_world();
+        w.objects[0].material.emissive = Color::new(0.5, 0.25, 0.0);
+        let r = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+        let c = w.color_from(&r, RayBudget::new(RECURSION_DEPTH, 0.0));
+        assert_eq!(c, Color::new(0.38066 + 0.5, 0.47583 + 0.25, 0.2855));
+    }
+
+    #[test]
+    fn emissive_object_seen_in_reflection() {
+        let mut w = World::new();
+        w.light = PointLight::new(Point::new(0.0, 10.0, -10.0), Color::new(0.0, 0.0, 0.0));
+        let mut mirror = Shape::new(Plane);
+        mirror.material.ambient = 0.0;
+        mirror.material.diffuse = 0.0;
+        mirror.material.specular = 0.0;
+        mirror.material.reflective = 1.0;
+        let mut lamp = Shape::new(Sphere);
+        lamp.set_transform(translation(0.0, 1.0, 1.0)).unwrap();
+        lamp.material.diffuse = 0.0;
+        lamp.material.ambient = 0.0;
+        lamp.material.specular = 0.0;
+        lamp.material.emissive = Color::new(1.0, 0.8, 0.2);
+        w.objects = vec![mirror, lamp];
+        let r = Ray::new(
+            Point::new(0.0, 1.0, -1.0),
+            Vector::new(0.0, -1.0, 1.0).normalize(),
+        );
+        let c = w.color_from(&r, RayBudget::new(RECURSION_DEPTH, 0.0));
+        assert_eq!(c, Color::new(1.0, 0.8, 0.2));
+    }
+
+    #[test]
+    fn emissive_objects_light_neighbours_with_samples() {
+        let mut w = World::new();
+        w.light = PointLight::new(Point::new(0.0, 10.0, -10.0), Color::new(0.0, 0.0, 0.0));
+        let floor = Shape::new(Plane);
+        let mut panel = Shape::new(Plane);
+        panel
+            .set_transform(
+                Builder::new()
+                    .rotation_x(PI)
+                    .translation(0.0, 1.0, 0.0)
+                    .transform(),
+            )
+            .unwrap();
+        panel.material.emissive = Color::new(1.0, 1.0, 1.0);
+        w.objects = vec![floor, panel];
+        let r = Ray::new(
+            Point::new(0.0, 0.5, -0.5),
+            Vector::new(0.0, -1.0, 1.0).normalize(),
+        );
+
+        let unlit = w.color_from(&r, RayBudget::new(RECURSION_DEPTH, 0.0));
+        assert_eq!(unlit, Color::new(0.0, 0.0, 0.0));
+
+        // The panel covers the whole sky, so every gathering ray sees it
+        w.emissive_samples = 8;
+        let lit = w.color_from(&r, RayBudget::new(RECURSION_DEPTH, 0.0));
+        assert_eq!(lit, Color::new(0.9, 0.9, 0.9));
+    }
+
+    #[test]
+    fn objects_can_opt_out_of_casting_shadows() {
+        let mut w = default_world();
+        let p = Point::new(-2.0, 2.0, -2.0);
+        let mut bulb = Shape::new(Sphere);
+        bulb.set_transform(translation(-5.0, 5.0, -5.0)).unwrap();
+        bulb.material.emissive = Color::new(1.0, 1.0, 1.0);
+        w.objects.push(bulb);
+        assert!(w.is_shadowed(p));
+        w.objects.last_mut().unwrap().material.casts_shadow = false;
+        assert!(!w.is_shadowed(p));
+    }
+
     #[test]
     fn color_from_hit() {
         let w = default_world();
         let r = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
-        let c = w.color_from(&r, RECURSION_DEPTH);
+        let c = w.color_from(&r, RayBudget::new(RECURSION_DEPTH, 0.0));
         assert_eq!(c, Color::new(0.38066, 0.47583, 0.2855));
     }
 
@@ -243,7 +869,7 @@ mod test {
         inner.material.ambient = 1.0;
         let inner = &w.objects[1];
         let r = Ray::new(Point::new(0.0, 0.0, 0.75), Vector::new(0.0, 0.0, -1.0));
-        let c = w.color_from(&r, RECURSION_DEPTH);
+        let c = w.color_from(&r, RayBudget::new(RECURSION_DEPTH, 0.0));
         assert_eq!(c, inner.material.color);
     }
 
@@ -288,7 +914,7 @@ mod test {
         let i = Intersection::new(4.0, &w.objects[1]);
         let xs = Intersections::new(vec![i.clone()]);
         let hit_info = HitInfo::prepare(&xs, &r, 0).unwrap();
-        let c = w.shade_hit(&hit_info, RECURSION_DEPTH);
+        let c = w.shade_hit(&hit_info, RayBudget::new(RECURSION_DEPTH, 0.0));
         assert_eq!(c, Color::new(0.1, 0.1, 0.1));
     }
 
@@ -302,7 +928,7 @@ mod test {
         let i = Intersection::new(1.0, shape);
         let xs = Intersections::new(vec![i.clone()]);
         let hit_info = HitInfo::prepare(&xs, &r, 0).unwrap();
-        let color = w.reflected_color(&hit_info, RECURSION_DEPTH);
+        let color = w.reflected_color(&hit_info, RayBudget::new(RECURSION_DEPTH, 0.0));
         assert_eq!(color, Color::new(0.0, 0.0, 0.0));
     }
 
@@ -321,7 +947,7 @@ mod test {
         let i = Intersection::new(2_f64.sqrt(), shape);
         let xs = Intersections::new(vec![i.clone()]);
         let hit_info = HitInfo::prepare(&xs, &r, 0).unwrap();
-        let color = w.reflected_color(&hit_info, RECURSION_DEPTH);
+        let color = w.reflected_color(&hit_info, RayBudget::new(RECURSION_DEPTH, 0.0));
         assert_eq!(color, Color::new(0.19032, 0.2379, 0.14274));
     }
 
@@ -340,7 +966,7 @@ mod test {
         let i = Intersection::new(2_f64.sqrt(), shape);
         let xs = Intersections::new(vec![i.clone()]);
         let hit_info = HitInfo::prepare(&xs, &r, 0).unwrap();
-        let color = w.shade_hit(&hit_info, RECURSION_DEPTH);
+        let color = w.shade_hit(&hit_info, RayBudget::new(RECURSION_DEPTH, 0.0));
         assert_eq!(color, Color::new(0.87677, 0.92436, 0.82918));
     }
 
@@ -357,7 +983,326 @@ mod test {
         upper.set_transform(translation(0.0, 1.0, 0.0)).unwrap();
         w.objects.push(upper);
         let r = Ray::new(Point::new(0.0, 0.0, 0.0), Vector::new(0.0, 1.0, 0.0));
-        w.color_from(&r, RECURSION_DEPTH);
+        w.color_from(&r, RayBudget::new(RECURSION_DEPTH, 0.0));
+    }
+
+    // A surface at the origin seen from above, with a lamp just off to the side
+    // of where a perfectly smooth surface would send the ray.
+    fn glossy_world(surface: Shape, lamp_y: f64) -> (World, Ray) {
+        let mut w = World::new();
+        w.light = PointLight::new(Point::new(0.0, 10.0, -10.0), Color::new(0.0, 0.0, 0.0));
+        let mut lamp = Shape::new(Sphere);
+        lamp.set_transform(
+            Builder::new()
+                .scaling(0.5, 0.5, 0.5)
+                .translation(1.2, lamp_y, 2.0)
+                .transform(),
+        )
+        .unwrap();
+        lamp.material.emissive = Color::new(1.0, 1.0, 1.0);
+        w.objects = vec![surface, lamp];
+        let r = Ray::new(
+            Point::new(0.0, 1.0, -1.0),
+            Vector::new(0.0, -1.0, 1.0).normalize(),
+        );
+        (w, r)
+    }
+
+    #[test]
+    fn rough_reflection_picks_up_nearby_objects() {
+        let mut mirror = Shape::new(Plane);
+        mirror.material.reflective = 1.0;
+        let (mut w, r) = glossy_world(mirror, 2.0);
+        assert_eq!(
+            w.color_from(&r, RayBudget::new(RECURSION_DEPTH, 0.0)),
+            Color::new(0.0, 0.0, 0.0)
+        );
+        // Roughness is only for highlights
+        w.objects[0].material.roughness = 0.5;
+        assert_eq!(
+            w.color_from(&r, RayBudget::new(RECURSION_DEPTH, 0.0)),
+            Color::new(0.0, 0.0, 0.0)
+        );
+
+        random::seed(1);
+        w.objects[0].material.glossiness = 0.5;
+        w.objects[0].material.glossy_samples = 64;
+        let c = w.color_from(&r, RayBudget::new(RECURSION_DEPTH, 0.0));
+        assert!(c.red() > 0.0 && c.red() < 1.0);
+    }
+
+    // A plane that counts the rays tested against it
+    #[derive(Debug, Clone)]
+    struct CountingPlane(Rc<Cell<usize>>);
+
+    impl PartialEq for CountingPlane {
+        fn eq(&self, other: &Self) -> bool {
+            Rc::ptr_eq(&self.0, &other.0)
+        }
+    }
+
+    impl ShapeModel for CountingPlane {
+        fn local_intersect(&self, local_ray: &Ray) -> Vec<f64> {
+            self.0.set(self.0.get() + 1);
+            Plane.local_intersect(local_ray)
+        }
+
+        fn local_normal_at(&self, local_point: Point) -> Vector {
+            Plane.local_normal_at(local_point)
+        }
+    }
+
+    #[test]
+    fn glossy_surfaces_only_fan_out_once() {
+        let rays = Rc::new(Cell::new(0));
+        let mut w = World::new();
+        w.light = PointLight::new(Point::new(0.0, 0.0, 0.0), WHITE);
+        for y in [-1.0, 1.0] {
+            let mut mirror = Shape::new(CountingPlane(rays.clone()));
+            mirror.material.reflective = 1.0;
+            mirror.material.glossiness = 0.2;
+            mirror.material.glossy_samples = 64;
+            mirror.set_transform(translation(0.0, y, 0.0)).unwrap();
+            w.objects.push(mirror);
+        }
+        let r = Ray::new(
+            Point::new(0.0, 0.0, 0.0),
+            Vector::new(0.0, 1.0, 0.2).normalize(),
+        );
+        w.color_from(&r, RayBudget::new(RECURSION_DEPTH, 0.0));
+        // Every ray is tested against both mirrors, and each hit casts a
+        // shadow ray as well
+        assert!(rays.get() <= 2 * 2 * (1 + 64 * RECURSION_DEPTH));
+    }
+
+    #[test]
+    fn rough_refraction_picks_up_nearby_objects() {
+        let mut glass = Shape::new(Plane);
+        glass.material.transparaency = 1.0;
+        let (mut w, r) = glossy_world(glass, -2.0);
+        assert_eq!(
+            w.color_from(&r, RayBudget::new(RECURSION_DEPTH, 0.0)),
+            Color::new(0.0, 0.0, 0.0)
+        );
+
+        random::seed(1);
+        w.objects[0].material.glossiness = 0.5;
+        w.objects[0].material.glossy_samples = 64;
+        let c = w.color_from(&r, RayBudget::new(RECURSION_DEPTH, 0.0));
+        assert!(c.red() > 0.0 && c.red() < 1.0);
+    }
+
+    #[test]
+    fn absorption_darkens_with_thickness() {
+        let mut w = World::new();
+        w.light = PointLight::new(Point::new(0.0, 10.0, -10.0), Color::new(0.0, 0.0, 0.0));
+        w.background = Background::Solid(WHITE);
+        let mut glass = Shape::new(Sphere);
+        glass.material.transparaency = 1.0;
+        glass.material.absorption = Color::new(0.5, 0.0, 0.0);
+        w.objects = vec![glass];
+
+        let through_centre = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+        let c = w.color_from(&through_centre, RayBudget::new(RECURSION_DEPTH, 0.0));
+        assert_eq!(c, Color::new((-1.0_f64).exp(), 1.0, 1.0));
+
+        let through_edge = Ray::new(Point::new(0.0, 0.6, -5.0), Vector::new(0.0, 0.0, 1.0));
+        let c = w.color_from(&through_edge, RayBudget::new(RECURSION_DEPTH, 0.0));
+        assert_eq!(c, Color::new((-0.8_f64).exp(), 1.0, 1.0));
+    }
+
+    #[test]
+    fn dispersion_splits_white_light() {
+        let mut w = World::new();
+        w.light = PointLight::new(Point::new(0.0, 10.0, -10.0), Color::new(0.0, 0.0, 0.0));
+        // Darker the more steeply a ray heads down
+        w.background = Background::Gradient {
+            bottom: BLACK,
+            top: WHITE,
+        };
+        let mut glass = Shape::new(Plane);
+        glass.material.transparaency = 1.0;
+        glass.material.refractive_index = 1.5;
+        w.objects = vec![glass];
+        let r = Ray::new(
+            Point::new(0.0, 1.0, 0.0),
+            Vector::new(1.0, -1.0, 0.0).normalize(),
+        );
+
+        let white = w.color_from(&r, RayBudget::new(RECURSION_DEPTH, 0.0));
+        assert_eq!(white.red(), white.green());
+        assert_eq!(white.green(), white.blue());
+
+        // Blue bends the most, so heads down most steeply
+        w.objects[0].material.dispersion = 0.05;
+        let split = w.color_from(&r, RayBudget::new(RECURSION_DEPTH, 0.0));
+        assert!(split.red() > split.green());
+        assert!(split.green() > split.blue());
+    }
+
+    #[test]
+    fn fog_dims_distant_surfaces() {
+        let mut w = World::new();
+        w.light = PointLight::new(Point::new(0.0, 10.0, -10.0), Color::new(0.0, 0.0, 0.0));
+        let mut lamp = Shape::new(Sphere);
+        lamp.material.emissive = WHITE;
+        w.objects = vec![lamp];
+        w.fog = Some(Medium::new(0.5, WHITE));
+        let r = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+        let c = w.color_from(&r, RayBudget::new(RECURSION_DEPTH, 0.0));
+        let faded = (-2.0_f64).exp();
+        assert_eq!(c, Color::new(faded, faded, faded));
+    }
+
+    #[test]
+    fn lit_fog_takes_its_colour() {
+        let mut w = World::new();
+        w.light = PointLight::new(Point::new(0.0, 10.0, -10.0), WHITE);
+        let mut fog = Medium::new(0.5, Color::new(0.6, 0.7, 0.8));
+        fog.steps = 256;
+        w.fog = Some(fog);
+        let r = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+        let c = w.color_from(&r, RayBudget::new(RECURSION_DEPTH, 0.0));
+        // The fog dims the light before scattering it, but not its colour
+        assert!(c.red() > 0.0);
+        assert!((c.green() / c.red() - 0.7 / 0.6).abs() < EQUALITY_EPSILON);
+        assert!((c.blue() / c.red() - 0.8 / 0.6).abs() < EQUALITY_EPSILON);
+    }
+
+    #[test]
+    fn volumes_only_scatter_light_that_reaches_them() {
+        let mut w = World::new();
+        w.light = PointLight::new(Point::new(0.0, 10.0, 0.0), WHITE);
+        w.volumes = vec![Volume::new(Shape::new(Sphere), Medium::new(1.0, WHITE))];
+        let r = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+
+        let lit = w.color_from(&r, RayBudget::new(RECURSION_DEPTH, 0.0));
+        assert!(lit.red() > 0.0);
+
+        let mut blocker = Shape::new(Plane);
+        blocker.set_transform(translation(0.0, 5.0, 0.0)).unwrap();
+        w.objects = vec![blocker];
+        let r = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+        assert_eq!(
+            w.color_from(&r, RayBudget::new(RECURSION_DEPTH, 0.0)),
+            Color::new(0.0, 0.0, 0.0)
+        );
+    }
+
+    // A unit sphere resting on the floor, and a ray hitting the floor just
+    // beside where they touch
+    fn contact_world() -> (World, Ray) {
+        let mut w = World::new();
+        w.light = PointLight::new(Point::new(-10.0, 10.0, -10.0), WHITE);
+        let mut ball = Shape::new(Sphere);
+        ball.set_transform(translation(0.0, 1.0, 0.0)).unwrap();
+        w.objects = vec![Shape::new(Plane), ball];
+        let r = Ray::new(
+            Point::new(0.5, 0.5, -5.0),
+            Vector::new(0.0, -0.5, 5.0).normalize(),
+        );
+        (w, r)
+    }
+
+    #[test]
+    fn ambient_occlusion_is_off_by_default() {
+        let (w, r) = contact_world();
+        let xs = w.intersect(&r);
+        let hit_info = HitInfo::prepare(&xs, &r, xs.hit().unwrap()).unwrap();
+        assert_eq!(hit_info.point, Point::new(0.5, 0.0, 0.0));
+        assert_eq!(w.ambient_visibility(&hit_info), 1.0);
+    }
+
+    #[test]
+    fn nearby_geometry_occludes() {
+        let (w, r) = contact_world();
+        let xs = w.intersect(&r);
+        let hit_info = HitInfo::prepare(&xs, &r, xs.hit().unwrap()).unwrap();
+
+        random::seed(2);
+        let near = World {
+            ambient_occlusion: Some(AmbientOcclusion::new(64, 2.0)),
+            ..w.clone()
+        };
+        let visibility = near.ambient_visibility(&hit_info);
+        assert!(visibility > 0.0 && visibility < 0.9);
+
+        // The ball is never closer than about 0.118
+        let nearer = World {
+            ambient_occlusion: Some(AmbientOcclusion::new(64, 0.05)),
+            ..w.clone()
+        };
+        assert_eq!(nearer.ambient_visibility(&hit_info), 1.0);
+    }
+
+    #[test]
+    fn occlusion_darkens_only_ambient() {
+        let (mut w, r) = contact_world();
+        w.objects[0].material.diffuse = 0.0;
+        w.objects[0].material.specular = 0.0;
+        let open = w.color_from(&r, RayBudget::new(RECURSION_DEPTH, 0.0));
+        assert_eq!(open, Color::new(0.1, 0.1, 0.1));
+
+        random::seed(2);
+        w.ambient_occlusion = Some(AmbientOcclusion::new(64, 2.0));
+        let occluded = w.color_from(&r, RayBudget::new(RECURSION_DEPTH, 0.0));
+        assert!(occluded.red() > 0.0 && occluded.red() < 0.09);
+    }
+
+    #[test]
+    fn light_dims_on_its_way_into_a_volume() {
+        let mut w = World::new();
+        w.light = PointLight::new(Point::new(0.0, 0.0, 10.0), WHITE);
+        w.volumes = vec![Volume::new(Shape::new(Sphere), Medium::new(1.0, WHITE))];
+
+        // Light scattered anywhere along the diameter has been through the
+        // same total length of the sphere
+        let r = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+        let c = w.color_from(&r, RayBudget::new(RECURSION_DEPTH, 0.0));
+        assert!((c.red() - 2.0 * (-2.0_f64).exp()).abs() < EQUALITY_EPSILON);
+
+        let from_inside = Ray::new(Point::new(0.0, 0.0, 0.0), Vector::new(0.0, 0.0, 1.0));
+        let c = w.color_from(&from_inside, RayBudget::new(RECURSION_DEPTH, 0.0));
+        assert!((c.red() - (-1.0_f64).exp()).abs() < EQUALITY_EPSILON);
+    }
+
+    #[test]
+    fn budget_runs_out_with_depth() {
+        let budget = RayBudget::new(1, 0.0);
+        let next = budget.bounce(1.0).unwrap();
+        assert_eq!(next, RayBudget::new(0, 0.0));
+        assert_eq!(next.bounce(1.0), None);
+    }
+
+    #[test]
+    fn budget_runs_out_with_weight() {
+        let budget = RayBudget::new(RECURSION_DEPTH, 0.01);
+        let dim = budget.bounce(0.5).unwrap().bounce(0.1).unwrap();
+        assert!((dim.weight - 0.05).abs() < EQUALITY_EPSILON);
+        assert_eq!(dim.bounce(0.1), None);
+    }
+
+    #[test]
+    fn faint_reflections_are_skipped() {
+        let mut w = default_world();
+        let mut shape = Shape::new(Plane);
+        shape.material.reflective = 0.5;
+        shape.set_transform(translation(0.0, -1.0, 0.0)).unwrap();
+        w.objects.push(shape);
+        let shape = &w.objects[2];
+        let r = Ray::new(
+            Point::new(0.0, 0.0, -3.0),
+            Vector::new(0.0, -(2_f64.sqrt()) / 2.0, 2_f64.sqrt() / 2.0),
+        );
+        let xs = Intersections::new(vec![Intersection::new(2_f64.sqrt(), shape)]);
+        let hit_info = HitInfo::prepare(&xs, &r, 0).unwrap();
+        let bright = RayBudget::new(RECURSION_DEPTH, 0.4);
+        assert_eq!(
+            w.reflected_color(&hit_info, bright),
+            Color::new(0.19032, 0.2379, 0.14274)
+        );
+        let dim = RayBudget::new(RECURSION_DEPTH, 0.6);
+        assert_eq!(w.reflected_color(&hit_info, dim), Color::new(0.0, 0.0, 0.0));
     }
 
     #[test]
@@ -375,7 +1320,7 @@ mod test {
         let i = Intersection::new(2_f64.sqrt(), shape);
         let xs = Intersections::new(vec![i.clone()]);
         let hit_info = HitInfo::prepare(&xs, &r, 0).unwrap();
-        let color = w.reflected_color(&hit_info, 0);
+        let color = w.reflected_color(&hit_info, RayBudget::new(0, 0.0));
         assert_eq!(color, Color::new(0.0, 0.0, 0.0));
     }
 
@@ -389,7 +1334,7 @@ mod test {
             Intersection::new(6.0, shape),
         ]);
         let hit_info = HitInfo::prepare(&xs, &r, 0).unwrap();
-        let color = w.refracted_color(&hit_info, RECURSION_DEPTH);
+        let color = w.refracted_color(&hit_info, RayBudget::new(RECURSION_DEPTH, 0.0));
         assert_eq!(color, Color::new(0.0, 0.0, 0.0));
     }
 
@@ -406,7 +1351,7 @@ mod test {
             Intersection::new(6.0, shape),
         ]);
         let hit_info = HitInfo::prepare(&xs, &r, 0).unwrap();
-        let color = w.refracted_color(&hit_info, 0);
+        let color = w.refracted_color(&hit_info, RayBudget::new(0, 0.0));
         assert_eq!(color, Color::new(0.0, 0.0, 0.0));
     }
 
@@ -426,7 +1371,7 @@ mod test {
             Intersection::new(2_f64.sqrt() / 2.0, shape),
         ]);
         let hit_info = HitInfo::prepare(&xs, &r, 1).unwrap();
-        let color = w.refracted_color(&hit_info, 5);
+        let color = w.refracted_color(&hit_info, RayBudget::new(5, 0.0));
         assert_eq!(color, Color::new(0.0, 0.0, 0.0));
     }
 
@@ -449,7 +1394,7 @@ mod test {
             Intersection::new(0.9899, a),
         ]);
         let hit_info = HitInfo::prepare(&xs, &r, 2).unwrap();
-        let color = w.refracted_color(&hit_info, 5);
+        let color = w.refracted_color(&hit_info, RayBudget::new(5, 0.0));
         assert_eq!(color, Color::new(0.0, 0.99888, 0.04725));
     }
 
@@ -473,7 +1418,7 @@ mod test {
         );
         let xs = Intersections::new(vec![Intersection::new(2_f64.sqrt(), floor)]);
         let hit_info = HitInfo::prepare(&xs, &r, 0).unwrap();
-        let color = w.shade_hit(&hit_info, 5);
+        let color = w.shade_hit(&hit_info, RayBudget::new(5, 0.0));
         assert_eq!(color, Color::new(0.93642, 0.68642, 0.68642));
     }
 
@@ -537,7 +1482,7 @@ mod test {
         let floor = &w.objects[w.objects.len() - 2];
         let xs = Intersections::new(vec![Intersection::new(2_f64.sqrt(), floor)]);
         let hit_info = HitInfo::prepare(&xs, &r, 0).unwrap();
-        let color = w.shade_hit(&hit_info, 5);
+        let color = w.shade_hit(&hit_info, RayBudget::new(5, 0.0));
         assert_eq!(color, Color::new(0.93391, 0.69643, 0.69243));
     }
 }